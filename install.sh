@@ -1,6 +1,6 @@
 #!/bin/sh
 if grep -q "ubuntu" "/etc/os-release"; then
-    sudo apt install libgtk-3-dev
+    sudo apt install libgtk-3-dev libsystemd-dev
     cargo build --release || exit 1
     version=$(grep "version" "Cargo.toml" | awk -F\" '{print $2}')
     if [ "$(getconf LONG_BIT)" = "64" ]; then arch=amd64; else arch=i386; fi
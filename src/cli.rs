@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+use std::path::Path;
+use systemd::analyze::Analyze;
+use systemd::dbus::{DbusHandle, LoadedUnit};
+use systemd::json::json_string;
+use systemd::status;
+use systemd::timespan;
+use Config;
+
+pub const USAGE: &str = "\
+Usage: systemd-manager [--user] [COMMAND [--json] [UNIT...]]
+
+Opens the graphical interface when no command is given.
+
+Commands:
+    list              List the unit files with their state and activity
+    status UNIT...    Show the status of units, like `systemctl status`
+    start UNIT...     Start units
+    stop UNIT...      Stop units
+    enable UNIT...    Enable the unit files of units
+    disable UNIT...   Disable the unit files of units
+    blame             List the units by the time they took to start during boot
+
+Options:
+    --user            Manage the units of the user instead of those of the system
+    --json            Print the output as JSON
+    -h, --help        Show this help
+";
+
+/// Runs a command given on the command line, without the graphical interface, and returns the
+/// exit status of the process: 1 when the command failed and 2 when it wasn't understood.
+pub fn run(config: &Config, args: &[String]) -> i32 {
+    let mut json = false;
+    let mut words = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                return 0;
+            }
+            x if x.starts_with('-') => return usage_error(&format!("Unrecognized option {:?}", x)),
+            x => words.push(x),
+        }
+    }
+
+    let (command, units) = match words.split_first() {
+        Some((command, units)) => (*command, units),
+        None => return usage_error("No command was given"),
+    };
+    match command {
+        "list" | "blame" if !units.is_empty() => {
+            return usage_error(&format!("{} doesn't take any units", command))
+        }
+        "status" | "start" | "stop" | "enable" | "disable" if units.is_empty() => {
+            return usage_error(&format!("{} needs at least one unit", command))
+        }
+        "list" | "blame" | "status" | "start" | "stop" | "enable" | "disable" => (),
+        x => return usage_error(&format!("Unknown command {:?}", x)),
+    }
+
+    let handle = match DbusHandle::try_new(config.bus_type) {
+        Ok(handle) => handle,
+        Err(error) => {
+            eprintln!("Unable to connect to the bus: {:?}", error);
+            return 1;
+        }
+    };
+    let result = match command {
+        "list" => list(&handle, json),
+        "blame" => blame(json),
+        "status" => status(&handle, units, json),
+        _ => act(&handle, command, units, json),
+    };
+    match result {
+        Ok(()) => 0,
+        Err(error) => {
+            eprintln!("{}", error);
+            1
+        }
+    }
+}
+
+fn usage_error(error: &str) -> i32 {
+    eprint!("{}\n\n{}", error, USAGE);
+    2
+}
+
+/// Lists the unit files with their enablement, and the states of those which are loaded, like
+/// `systemctl list-unit-files` and `systemctl list-units` together.
+fn list(handle: &DbusHandle, json: bool) -> Result<(), String> {
+    let loaded: HashMap<String, LoadedUnit> = handle
+        .list_units()
+        .map_err(|error| format!("Unable to list the units: {:?}", error))?
+        .into_iter()
+        .map(|x| (x.name.clone(), x))
+        .collect();
+
+    let rows: Vec<Vec<String>> = handle
+        .try_list_unit_files()
+        .map_err(|error| format!("Unable to list the unit files: {:?}", error))?
+        .iter()
+        .map(|unit| {
+            let name = Path::new(&unit.name)
+                .file_name()
+                .map_or_else(|| unit.name.clone(), |x| x.to_string_lossy().into_owned());
+            let (load, active, sub, description) = match loaded.get(&name) {
+                Some(x) => (
+                    x.load_state.as_str(),
+                    x.active_state.as_str(),
+                    x.sub_state.as_str(),
+                    x.description.as_str(),
+                ),
+                None => ("-", "inactive", "dead", ""),
+            };
+            vec![
+                name.clone(),
+                unit.state.name().to_owned(),
+                load.to_owned(),
+                active.to_owned(),
+                sub.to_owned(),
+                description.to_owned(),
+            ]
+        })
+        .collect();
+
+    if json {
+        let objects = rows.iter().map(|row| {
+            json_object(&[
+                ("unit", json_string(&row[0])),
+                ("state", json_string(&row[1])),
+                ("load", json_string(&row[2])),
+                ("active", json_string(&row[3])),
+                ("sub", json_string(&row[4])),
+                ("description", json_string(&row[5])),
+            ])
+        });
+        print_json_array(objects);
+    } else {
+        let headers = ["UNIT", "STATE", "LOAD", "ACTIVE", "SUB", "DESCRIPTION"];
+        print_table(&headers, &rows);
+    }
+    Ok(())
+}
+
+/// Lists the units from the slowest to start during boot, like `systemd-analyze blame`.
+fn blame(json: bool) -> Result<(), String> {
+    let blame = Analyze::blame()?;
+    if json {
+        let objects = blame.iter().rev().map(|x| {
+            json_object(&[
+                ("unit", json_string(&x.service)),
+                ("time_ms", x.time.to_string()),
+            ])
+        });
+        print_json_array(objects);
+    } else {
+        let rows: Vec<Vec<String>> = blame
+            .iter()
+            .rev()
+            .map(|x| {
+                vec![
+                    timespan::format(u64::from(x.time) * 1000),
+                    x.service.clone(),
+                ]
+            })
+            .collect();
+        print_table(&["TIME", "UNIT"], &rows);
+    }
+    Ok(())
+}
+
+/// Shows what systemd reports about each unit, with the same fields as the status panel.
+fn status(handle: &DbusHandle, units: &[&str], json: bool) -> Result<(), String> {
+    let mut statuses = Vec::new();
+    for unit in units {
+        let properties = handle
+            .get_unit_properties(unit, "")
+            .map_err(|error| format!("Unable to get the status of {}: {:?}", unit, error))?;
+        statuses.push((unit, status::status_fields(&properties)));
+    }
+
+    if json {
+        let objects = statuses.iter().map(|(unit, fields)| {
+            let mut members = vec![("Unit", json_string(unit))];
+            members.extend(fields.iter().map(|x| (x.0, json_string(&x.1))));
+            json_object(&members)
+        });
+        print_json_array(objects);
+    } else {
+        for (index, (unit, fields)) in statuses.iter().enumerate() {
+            if index > 0 {
+                println!();
+            }
+            println!("{}", unit);
+            let width = fields.iter().map(|x| x.0.len()).max().unwrap_or(0);
+            for (field, value) in fields {
+                // Values spanning several lines stay aligned with the first line.
+                let value = value.replace('\n', &format!("\n    {:width$}  ", "", width = width));
+                println!("    {:>width$}: {}", field, value, width = width);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Starts, stops, enables or disables every unit, and fails if any of them couldn't be. The
+/// result for each unit is printed, while the details of errors go to the standard error.
+fn act(handle: &DbusHandle, command: &str, units: &[&str], json: bool) -> Result<(), String> {
+    let results: Vec<(&str, Option<String>)> = units
+        .iter()
+        .map(|&unit| {
+            let error = match command {
+                "start" => handle.start_unit(unit),
+                "stop" => handle.stop_unit(unit),
+                "enable" => handle.enable_unit_files(unit),
+                _ => handle.disable_unit_files(unit),
+            };
+            (unit, error)
+        })
+        .collect();
+    let failed = results.iter().filter(|x| x.1.is_some()).count();
+
+    // Like `systemctl`, changes to the enablement of units are followed by a reload.
+    let mut reload_error = None;
+    if (command == "enable" || command == "disable") && failed < units.len() {
+        reload_error = handle.reload();
+    }
+
+    if json {
+        let objects = results.iter().map(|(unit, error)| {
+            json_object(&[
+                ("unit", json_string(unit)),
+                ("command", json_string(command)),
+                ("success", error.is_none().to_string()),
+                (
+                    "error",
+                    error
+                        .as_ref()
+                        .map_or_else(|| String::from("null"), |x| json_string(x)),
+                ),
+            ])
+        });
+        print_json_array(objects);
+    } else {
+        let rows: Vec<Vec<String>> = results
+            .iter()
+            .map(|(unit, error)| {
+                let result = if error.is_some() { "failed" } else { "done" };
+                vec![unit.to_string(), result.to_owned()]
+            })
+            .collect();
+        print_table(&["UNIT", "RESULT"], &rows);
+    }
+
+    match (failed, reload_error) {
+        (0, None) => Ok(()),
+        (0, Some(error)) => Err(error),
+        _ => Err(format!(
+            "Unable to {} {} of {} units",
+            command,
+            failed,
+            units.len()
+        )),
+    }
+}
+
+/// Prints the rows under the headers, with each column as wide as its widest cell.
+fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = headers.iter().map(|x| x.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let print_row = |cells: Vec<&str>| {
+        let last = cells.len() - 1;
+        let line: Vec<String> = cells
+            .iter()
+            .enumerate()
+            .map(|(index, cell)| match index {
+                x if x == last => cell.to_string(),
+                _ => format!("{:width$}", cell, width = widths[index]),
+            })
+            .collect();
+        println!("{}", line.join("  "));
+    };
+    print_row(headers.to_vec());
+    for row in rows {
+        print_row(row.iter().map(String::as_str).collect());
+    }
+}
+
+/// Writes the members as a JSON object. The values must already be written as JSON.
+fn json_object(members: &[(&str, String)]) -> String {
+    let members: Vec<String> = members
+        .iter()
+        .map(|(name, value)| format!("{}:{}", json_string(name), value))
+        .collect();
+    format!("{{{}}}", members.join(","))
+}
+
+/// Prints the objects as a JSON array, one object per line.
+fn print_json_array<I: Iterator<Item = String>>(objects: I) {
+    let objects: Vec<String> = objects.collect();
+    if objects.is_empty() {
+        println!("[]");
+    } else {
+        println!("[\n{}\n]", objects.join(",\n"));
+    }
+}
@@ -1,28 +1,74 @@
 use std::env;
+use std::process;
 
+#[cfg(feature = "gui")]
 extern crate gdk;
+#[cfg(feature = "gui")]
 extern crate gtk;
+mod cli; // Subcommands run from the command line without the GUI
+#[cfg(feature = "gui")]
 mod systemd_gui; // Contains all of the heavy GUI-related work
 mod systemd {
     pub mod analyze; // Support for systemd-analyze
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub mod calendar; // Calendar event expressions of timers, like `systemd-analyze calendar`
     pub mod dbus; // The dbus backend for systemd
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub mod dependencies; // Relations between units, like `systemctl list-dependencies`
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub mod graph; // Dependency graphs, like `systemd-analyze dot`
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub mod history; // Boot timings recorded across boots
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub mod journal; // Entries of the journal, read through sd-journal like `journalctl`
+    pub mod json; // Values written as JSON, shared by the journal export and the command line
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub mod processes; // Processes running within the control group of a unit
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub mod resources; // Resource usage sampled from unit properties and control groups
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub mod security; // Sandboxing exposure of services, like `systemd-analyze security`
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub mod sockets; // Listen addresses and connections of sockets, like `systemctl list-sockets`
+    pub mod status; // Summarizes unit properties like `systemctl status`
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub mod timeline; // Boot timelines, like `systemd-analyze plot`
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub mod timers; // Next and last elapses of timers, like `systemctl list-timers`
+    pub mod timespan; // Time spans written the way systemd does, such as `1min 2.345s`
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub mod transient; // Commands run as transient units, like `systemd-run`
 }
 
 fn main() {
     let mut config = Config::default();
+    let mut args = Vec::new();
     for arg in env::args().skip(1) {
         match arg.as_ref() {
             "--user" => {
                 config.bus_type = dbus::BusType::Session;
             }
-            x => {
-                panic!("Unrecognized CLI argument {:?}", x);
-            }
+            _ => args.push(arg),
         }
     }
+    if args.is_empty() {
+        launch(config);
+    } else {
+        process::exit(cli::run(&config, &args));
+    }
+}
+
+#[cfg(feature = "gui")]
+fn launch(config: Config) {
     systemd_gui::launch(config);
 }
 
+#[cfg(not(feature = "gui"))]
+fn launch(_config: Config) {
+    eprint!("Built without the graphical interface\n\n{}", cli::USAGE);
+    process::exit(2);
+}
+
 #[derive(Debug, Clone)]
 pub struct Config {
     /// The bus type to use. Defaults to System, can be instead Session to access the user dbus.
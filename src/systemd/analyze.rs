@@ -1,4 +1,7 @@
+use std::collections::HashMap;
 use std::process::Command;
+use systemd::dbus::{DbusHandle, Properties};
+use systemd::timespan;
 
 #[derive(Clone)]
 pub struct Analyze {
@@ -7,37 +10,276 @@ pub struct Analyze {
 }
 
 impl Analyze {
-    /// Returns the results of `systemd-analyze blame`
-    pub fn blame() -> Vec<Analyze> {
-        fn parse_time(input: &str) -> u32 {
-            if input.ends_with("ms") {
-                input[0..input.len() - 2].parse::<u32>().unwrap_or(0)
-            } else if input.ends_with('s') {
-                (input[0..input.len() - 1].parse::<f32>().unwrap_or(0f32) * 1000f32) as u32
-            } else if input.ends_with("min") {
-                input[0..input.len() - 3].parse::<u32>().unwrap_or(0) * 3600000
-            } else {
-                0u32
+    /// Returns the results of `systemd-analyze blame`, from the fastest unit to the slowest.
+    /// Fails if `systemd-analyze` can't be run, or if its output can't be understood.
+    pub fn blame() -> Result<Vec<Analyze>, String> {
+        let output = Command::new("systemd-analyze")
+            .arg("blame")
+            .output()
+            .map_err(|error| format!("Unable to run systemd-analyze: {}", error))?;
+        if !output.status.success() {
+            return Err(format!(
+                "systemd-analyze blame failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .rev()
+            .filter(|x| !x.trim().is_empty())
+            .map(|line| {
+                // The time span may have several components, such as `1min 2.345s`, while the
+                // unit name is always the last word.
+                let line = line.trim();
+                let split = line
+                    .rfind(char::is_whitespace)
+                    .ok_or_else(|| format!("Invalid line from systemd-analyze: {:?}", line))?;
+                let usec = timespan::parse(&line[..split])?;
+                Ok(Analyze {
+                    time: (usec / 1000).min(u64::from(u32::MAX)) as u32,
+                    service: line[split..].trim().to_owned(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// How long each phase of the boot took, in microseconds, as reported by `systemd-analyze time`.
+/// Phases which weren't recorded, such as the firmware on virtual machines or everything but
+/// userspace for user managers, are `None`.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+#[derive(Clone, Debug)]
+pub struct BootTimes {
+    pub firmware: Option<u64>,
+    pub loader: Option<u64>,
+    pub kernel: Option<u64>,
+    pub initrd: Option<u64>,
+    pub userspace: u64,
+    /// The realtime timestamp at which the kernel started, in microseconds since the epoch.
+    pub kernel_started: Option<u64>,
+}
+
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+impl BootTimes {
+    /// Computes the phases of the boot from the timestamps of the manager. The firmware and
+    /// loader timestamps count backwards from the start of the kernel, while the others count
+    /// forward from it. Returns `None` while the boot hasn't finished yet.
+    pub fn from_properties(properties: &Properties) -> Option<BootTimes> {
+        let timestamp = |name: &str| properties.u64(name).filter(|&x| x != 0);
+        let finish = timestamp("FinishTimestampMonotonic")?;
+        let userspace = timestamp("UserspaceTimestampMonotonic").unwrap_or(0);
+        let loader = timestamp("LoaderTimestampMonotonic");
+        let initrd = timestamp("InitRDTimestampMonotonic");
+        let kernel_started = timestamp("KernelTimestamp");
+
+        Some(BootTimes {
+            firmware: timestamp("FirmwareTimestampMonotonic")
+                .map(|firmware| firmware.saturating_sub(loader.unwrap_or(0))),
+            loader,
+            // The kernel only runs until the initrd takes over, if there is one.
+            kernel: kernel_started.map(|_| initrd.unwrap_or(userspace)),
+            initrd: initrd.map(|initrd| userspace.saturating_sub(initrd)),
+            userspace: finish.saturating_sub(userspace),
+            kernel_started,
+        })
+    }
+
+    /// The phases which were recorded, in the order they happened.
+    pub fn phases(&self) -> Vec<(&'static str, u64)> {
+        let mut phases = Vec::new();
+        let optional = [
+            ("firmware", self.firmware),
+            ("loader", self.loader),
+            ("kernel", self.kernel),
+            ("initrd", self.initrd),
+        ];
+        for &(name, duration) in optional.iter() {
+            if let Some(duration) = duration {
+                phases.push((name, duration));
             }
         }
+        phases.push(("userspace", self.userspace));
+        phases
+    }
+
+    /// The time from powering on the machine until the boot finished.
+    pub fn total(&self) -> u64 {
+        self.phases().iter().map(|&(_, duration)| duration).sum()
+    }
 
-        String::from_utf8(
-            Command::new("systemd-analyze")
-                .arg("blame")
-                .output()
-                .unwrap()
-                .stdout,
+    /// Summarizes the boot on a single line, like `systemd-analyze time` does.
+    pub fn summary(&self) -> String {
+        let phases: Vec<String> = self
+            .phases()
+            .iter()
+            .map(|&(name, duration)| format!("{} ({})", timespan::format(duration), name))
+            .collect();
+        format!(
+            "Startup finished in {} = {}",
+            phases.join(" + "),
+            timespan::format(self.total())
         )
-        .unwrap()
-        .lines()
-        .rev()
-        .map(|x| {
-            let mut iterator = x.split_whitespace();
-            Analyze {
-                time: parse_time(iterator.next().unwrap()),
-                service: String::from(iterator.next().unwrap()),
-            }
+    }
+}
+
+/// A unit on the critical chain of another unit, like those listed by
+/// `systemd-analyze critical-chain`.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+#[derive(Clone, Debug)]
+pub struct ChainLink {
+    pub unit: String,
+    /// How far the unit is from the unit whose chain was requested.
+    pub depth: usize,
+    /// When the unit started activating if it had to wait, or else when it became active, in
+    /// microseconds since userspace started.
+    pub time: Option<u64>,
+    /// How long the unit took to become active, if it had to wait for anything.
+    pub duration: Option<u64>,
+}
+
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+impl ChainLink {
+    /// Describes the timing of the unit the way `systemd-analyze critical-chain` does, such
+    /// as `@1.234s +56ms`.
+    pub fn annotation(&self) -> String {
+        match (self.time, self.duration) {
+            (Some(time), Some(duration)) => format!(
+                "@{} +{}",
+                timespan::format(time),
+                timespan::format(duration)
+            ),
+            (Some(time), None) => format!("@{}", timespan::format(time)),
+            _ => String::new(),
+        }
+    }
+}
+
+/// The activation timestamps of a unit and the units it is ordered after.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+struct UnitTimes {
+    activating: u64,
+    activated: u64,
+    after: Vec<String>,
+}
+
+/// Follows the ordering dependencies of a unit to find the chain of units which delayed it.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+struct CriticalChain<'a> {
+    handle: &'a DbusHandle,
+    userspace: u64,
+    finish: u64,
+    units: HashMap<String, UnitTimes>,
+    visited: Vec<String>,
+    links: Vec<ChainLink>,
+}
+
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+impl<'a> CriticalChain<'a> {
+    fn times(&mut self, unit: &str) -> Result<&UnitTimes, String> {
+        if !self.units.contains_key(unit) {
+            let properties = self
+                .handle
+                .get_unit_properties(unit, "org.freedesktop.systemd1.Unit")
+                .map_err(|error| {
+                    format!("Unable to get the properties of {}: {:?}", unit, error)
+                })?;
+            let times = UnitTimes {
+                activating: properties
+                    .u64("InactiveExitTimestampMonotonic")
+                    .unwrap_or(0),
+                activated: properties.u64("ActiveEnterTimestampMonotonic").unwrap_or(0),
+                after: properties.strings("After"),
+            };
+            self.units.insert(unit.to_owned(), times);
+        }
+        Ok(&self.units[unit])
+    }
+
+    /// Units which were activated before the boot started or after it finished didn't take
+    /// part in it.
+    fn activated_during_boot(&mut self, unit: &str) -> Result<Option<u64>, String> {
+        let finish = self.finish;
+        let activated = self.times(unit)?.activated;
+        if activated != 0 && (finish == 0 || activated <= finish) {
+            Ok(Some(activated))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn link(&mut self, unit: &str, depth: usize) -> Result<ChainLink, String> {
+        let userspace = self.userspace;
+        let times = self.times(unit)?;
+        // Like `systemd-analyze critical-chain`, units which had to wait are shown from when
+        // they started activating.
+        let (time, duration) = if times.activating != 0 && times.activated > times.activating {
+            (
+                Some(times.activating.saturating_sub(userspace)),
+                Some(times.activated - times.activating),
+            )
+        } else {
+            let time = Some(times.activated)
+                .filter(|&x| x != 0)
+                .map(|x| x.saturating_sub(userspace));
+            (time, None)
+        };
+        Ok(ChainLink {
+            unit: unit.to_owned(),
+            depth,
+            time,
+            duration,
         })
-        .collect::<Vec<Analyze>>()
     }
+
+    /// Appends the units the given unit was ordered after which became active last, as they
+    /// are the ones it was waiting for, and then follows their own chains.
+    fn follow(&mut self, unit: &str, depth: usize) -> Result<(), String> {
+        let after = self.times(unit)?.after.clone();
+        let mut candidates: Vec<(String, u64)> = Vec::new();
+        for dependency in after {
+            if let Some(time) = self.activated_during_boot(&dependency)? {
+                candidates.push((dependency, time));
+            }
+        }
+        let latest = match candidates.iter().map(|&(_, time)| time).max() {
+            Some(latest) => latest,
+            None => return Ok(()),
+        };
+        candidates.retain(|&(_, time)| time == latest);
+        candidates.sort();
+
+        for (dependency, _) in candidates {
+            let link = self.link(&dependency, depth)?;
+            self.links.push(link);
+            if !self.visited.contains(&dependency) {
+                self.visited.push(dependency.clone());
+                self.follow(&dependency, depth + 1)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Computes the chain of units which the given unit had to wait for while booting, like
+/// `systemd-analyze critical-chain`. The unit itself is the first link of the chain, and
+/// each following link is one of the units which the link at the previous depth waited for.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub fn critical_chain(handle: &DbusHandle, unit: &str) -> Result<Vec<ChainLink>, String> {
+    let manager = handle
+        .get_manager_properties()
+        .map_err(|error| format!("Unable to get the properties of the manager: {:?}", error))?;
+    let mut chain = CriticalChain {
+        handle,
+        userspace: manager.u64("UserspaceTimestampMonotonic").unwrap_or(0),
+        finish: manager.u64("FinishTimestampMonotonic").unwrap_or(0),
+        units: HashMap::new(),
+        visited: vec![unit.to_owned()],
+        links: Vec::new(),
+    };
+
+    let root = chain.link(unit, 0)?;
+    chain.links.push(root);
+    chain.follow(unit, 1)?;
+    Ok(chain.links)
 }
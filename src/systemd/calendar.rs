@@ -0,0 +1,820 @@
+extern crate libc;
+
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+/// The names of the days of the week, starting from Monday like systemd does.
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+/// The shorthands accepted in place of a full expression, along with what they stand for.
+const SHORTHANDS: [(&str, &str); 9] = [
+    ("minutely", "*-*-* *:*:00"),
+    ("hourly", "*-*-* *:00:00"),
+    ("daily", "*-*-* 00:00:00"),
+    ("monthly", "*-*-01 00:00:00"),
+    ("weekly", "Mon *-*-* 00:00:00"),
+    ("yearly", "*-01-01 00:00:00"),
+    ("annually", "*-01-01 00:00:00"),
+    ("quarterly", "*-01,04,07,10-01 00:00:00"),
+    ("semiannually", "*-01,07-01 00:00:00"),
+];
+
+/// A date as its year, month and day, and a time as its hour, minute and second.
+type Date = (u32, u32, u32);
+type Time = (u32, u32, u32);
+
+/// Where the time zones which may end an expression are installed.
+const ZONEINFO: &str = "/usr/share/zoneinfo";
+
+/// Elapses are searched for up to the last year systemd accepts.
+const MIN_YEAR: u32 = 1970;
+const MAX_YEAR: u32 = 2199;
+
+/// A single item of a comma separated list, such as `5`, `1..10`, `0/15` or `8..18/2`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Component {
+    start: u32,
+    stop: Option<u32>,
+    /// Repeats the value every so many units from the start, or `0` to match the start once.
+    repeat: u32,
+}
+
+impl Component {
+    fn matches(self, value: u32) -> bool {
+        if value < self.start || self.stop.is_some_and(|stop| value > stop) {
+            return false;
+        }
+        match (self.stop, self.repeat) {
+            (_, repeat) if repeat > 0 => (value - self.start).is_multiple_of(repeat),
+            (Some(_), _) => true,
+            (None, _) => value == self.start,
+        }
+    }
+
+    fn format(self, width: usize) -> String {
+        let mut output = format!("{:0width$}", self.start, width = width);
+        if let Some(stop) = self.stop {
+            output.push_str(&format!("..{:0width$}", stop, width = width));
+        }
+        if self.repeat > 0 {
+            output.push_str(&format!("/{}", self.repeat));
+        }
+        output
+    }
+}
+
+/// The values a field of the expression matches. An empty list matches every value, and is
+/// written as `*`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Field(Vec<Component>);
+
+impl Field {
+    /// A field which only matches the given value.
+    fn exact(value: u32) -> Field {
+        Field(vec![Component {
+            start: value,
+            stop: None,
+            repeat: 0,
+        }])
+    }
+
+    /// Whether the field only matches a single value.
+    fn is_exact(&self) -> bool {
+        match self.0.as_slice() {
+            [component] => component.stop.is_none() && component.repeat == 0,
+            _ => false,
+        }
+    }
+
+    fn matches(&self, value: u32) -> bool {
+        self.0.is_empty() || self.0.iter().any(|x| x.matches(value))
+    }
+
+    fn format(&self, width: usize) -> String {
+        if self.0.is_empty() {
+            return String::from("*");
+        }
+        let items: Vec<String> = self.0.iter().map(|x| x.format(width)).collect();
+        items.join(",")
+    }
+
+    /// Parses a comma separated list of components, checking that every value is within the
+    /// given bounds. `*` matches every value, and `*/5` repeats from the lowest value.
+    fn parse(input: &str, name: &str, min: u32, max: u32) -> Result<Field, String> {
+        if input == "*" {
+            return Ok(Field(Vec::new()));
+        }
+        let value = |input: &str| -> Result<u32, String> {
+            if input.is_empty() || !input.chars().all(|x| x.is_ascii_digit()) {
+                return Err(format!("Invalid {}: {:?}", name, input));
+            }
+            match input.parse::<u32>() {
+                Ok(value) if value >= min && value <= max => Ok(value),
+                _ => Err(format!(
+                    "The {} {:?} is not within {}..{}",
+                    name, input, min, max
+                )),
+            }
+        };
+
+        let mut components = Vec::new();
+        for item in input.split(',') {
+            let (range, repeat) = match item.find('/') {
+                Some(slash) => {
+                    let repeat = &item[slash + 1..];
+                    match repeat.parse::<u32>() {
+                        Ok(repeat) if repeat > 0 => (&item[..slash], repeat),
+                        _ => return Err(format!("Invalid repetition of the {}: {:?}", name, item)),
+                    }
+                }
+                None => (item, 0),
+            };
+            let (start, stop) = match range.find("..") {
+                _ if range == "*" && repeat > 0 => (min, None),
+                Some(dots) => (value(&range[..dots])?, Some(value(&range[dots + 2..])?)),
+                None => (value(range)?, None),
+            };
+            if stop.is_some_and(|stop| stop < start) {
+                return Err(format!(
+                    "The {} range {:?} ends before it starts",
+                    name, range
+                ));
+            }
+            components.push(Component {
+                start,
+                stop,
+                repeat,
+            });
+        }
+        components.sort();
+        components.dedup();
+        Ok(Field(components))
+    }
+}
+
+/// A calendar event expression, as used by `OnCalendar=` in timers, such as
+/// `Mon..Fri *-*-* 09:00:00` or `weekly`. Fractional seconds and time zones other than UTC are
+/// not supported, see `unsupported`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CalendarSpec {
+    /// The days of the week the event happens on, as a bit per day starting from Monday. No bit
+    /// set means every day.
+    weekdays: u8,
+    year: Field,
+    month: Field,
+    day: Field,
+    /// Whether the days are counted backwards from the last day of the month, like `*-02~03`.
+    end_of_month: bool,
+    hour: Field,
+    minute: Field,
+    second: Field,
+    utc: bool,
+}
+
+/// Parses a calendar event expression the way `systemd-analyze calendar` does. The date and
+/// time may each be left out, in which case every date matches and the time is midnight.
+pub fn parse(input: &str) -> Result<CalendarSpec, String> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Err(String::from("The calendar expression is empty"));
+    }
+
+    let mut words: Vec<&str> = trimmed.split_whitespace().collect();
+    let utc = words.last().is_some_and(|x| x.eq_ignore_ascii_case("UTC"));
+    if utc {
+        words.pop();
+    }
+    if words.len() == 1 {
+        if let Some(&(_, expansion)) = SHORTHANDS.iter().find(|&&(name, _)| name == words[0]) {
+            return parse(expansion).map(|spec| CalendarSpec { utc, ..spec });
+        }
+        if let Some(timestamp) = words[0].strip_prefix('@') {
+            return parse_timestamp(timestamp);
+        }
+    }
+
+    let mut words = words.into_iter().peekable();
+    let mut weekdays = 0;
+    if let Some(word) = words.next_if(|x| x.starts_with(|x: char| x.is_ascii_alphabetic())) {
+        weekdays = parse_weekdays(word)?;
+    }
+    let date = words.next_if(|x| !x.contains(':'));
+    let time = words.next();
+    if let Some(word) = words.next() {
+        return Err(format!("Unexpected {:?} in the calendar expression", word));
+    }
+    if weekdays == 0 && date.is_none() && time.is_none() {
+        return Err(format!("Invalid calendar expression: {:?}", input));
+    }
+
+    let (year, month, day, end_of_month) = match date {
+        Some(date) => parse_date(date)?,
+        None => (
+            Field(Vec::new()),
+            Field(Vec::new()),
+            Field(Vec::new()),
+            false,
+        ),
+    };
+    let (hour, minute, second) = match time {
+        Some(time) => parse_time(time)?,
+        None => (Field::exact(0), Field::exact(0), Field::exact(0)),
+    };
+    Ok(CalendarSpec {
+        weekdays,
+        year,
+        month,
+        day,
+        end_of_month,
+        hour,
+        minute,
+        second,
+        utc,
+    })
+}
+
+/// Tells why an expression which `parse` rejects is still accepted by systemd, when it only
+/// uses what can't be evaluated here: a time zone other than UTC, such as `Europe/Berlin`, or
+/// fractions of seconds, such as `*:*:0/0.5`. Returns `None` for expressions which are invalid.
+pub fn unsupported(input: &str) -> Option<String> {
+    let mut words: Vec<&str> = input.split_whitespace().collect();
+    let zone = words.last().copied().filter(|x| is_time_zone(x));
+    if zone.is_some() {
+        words.pop();
+    }
+
+    // The rest of the expression must be valid once the fractions of seconds are left out.
+    let mut fractional = false;
+    let mut whole = Vec::with_capacity(words.len());
+    for word in words {
+        match word.rfind(':') {
+            Some(colon) if word[colon + 1..].contains('.') => {
+                fractional = true;
+                whole.push(format!(
+                    "{}:{}",
+                    &word[..colon],
+                    whole_seconds(&word[colon + 1..])?
+                ));
+            }
+            _ => whole.push(word.to_owned()),
+        }
+    }
+    if (zone.is_none() && !fractional) || parse(&whole.join(" ")).is_err() {
+        return None;
+    }
+    match zone {
+        Some(zone) => Some(format!("it uses the time zone {}", zone)),
+        None => Some(String::from("it uses fractions of seconds")),
+    }
+}
+
+/// Whether a time zone of that name is installed, such as `Europe/Berlin`.
+fn is_time_zone(name: &str) -> bool {
+    if name.starts_with('/') || name.split('/').any(|x| x.is_empty() || x.starts_with('.')) {
+        return false;
+    }
+    let mut magic = [0; 4];
+    fs::File::open(Path::new(ZONEINFO).join(name))
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok()
+        && &magic == b"TZif"
+}
+
+/// Rounds the seconds of a time down to whole seconds, such as `05.250` to `05`. Repetitions of
+/// less than a second are rounded up to one.
+fn whole_seconds(input: &str) -> Option<String> {
+    let whole = |number: &str| -> Option<u32> {
+        match number.find('.') {
+            Some(dot) if number[dot + 1..].chars().all(|x| x.is_ascii_digit()) => {
+                number[..dot].parse().ok()
+            }
+            Some(_) => None,
+            None => number.parse().ok(),
+        }
+    };
+    let mut items = Vec::new();
+    for item in input.split(',') {
+        let (range, repeat) = match item.find('/') {
+            Some(slash) => (&item[..slash], Some(&item[slash + 1..])),
+            None => (item, None),
+        };
+        let mut output = match range.find("..") {
+            _ if range == "*" => String::from("*"),
+            Some(dots) => format!("{}..{}", whole(&range[..dots])?, whole(&range[dots + 2..])?),
+            None => whole(range)?.to_string(),
+        };
+        if let Some(repeat) = repeat {
+            output.push_str(&format!("/{}", whole(repeat)?.max(1)));
+        }
+        items.push(output);
+    }
+    Some(items.join(","))
+}
+
+/// Parses a list of days of the week such as `Mon,Wed` or `Mon..Fri`, into a bit per day.
+fn parse_weekdays(input: &str) -> Result<u8, String> {
+    let day = |input: &str| -> Result<u32, String> {
+        WEEKDAYS
+            .iter()
+            .position(|name| {
+                input.len() >= 3
+                    && name.len() >= input.len()
+                    && name[..input.len()].eq_ignore_ascii_case(input)
+            })
+            .map(|x| x as u32)
+            .ok_or_else(|| format!("Invalid day of the week: {:?}", input))
+    };
+
+    let mut weekdays = 0;
+    // A trailing comma is allowed, as in `Wed, 17:48`.
+    for item in input.split(',').filter(|x| !x.is_empty()) {
+        let range = match item.find("..") {
+            Some(dots) => Some((&item[..dots], &item[dots + 2..])),
+            None => item
+                .find('-')
+                .map(|dash| (&item[..dash], &item[dash + 1..])),
+        };
+        let (start, stop) = match range {
+            Some((start, stop)) => (day(start)?, day(stop)?),
+            None => (day(item)?, day(item)?),
+        };
+        // Ranges such as `Sat..Mon` wrap around the end of the week.
+        let mut current = start;
+        loop {
+            weekdays |= 1 << current;
+            if current == stop {
+                break;
+            }
+            current = (current + 1) % 7;
+        }
+    }
+    Ok(weekdays)
+}
+
+/// Parses a date such as `2024-*-01`, `*-12-25` or `02~03`, where the year may be left out.
+fn parse_date(input: &str) -> Result<(Field, Field, Field, bool), String> {
+    let (rest, day, end_of_month) = match input.find('~') {
+        Some(tilde) => (&input[..tilde], &input[tilde + 1..], true),
+        None => match input.rfind('-') {
+            Some(dash) => (&input[..dash], &input[dash + 1..], false),
+            None => return Err(format!("Invalid date: {:?}", input)),
+        },
+    };
+    let (year, month) = match rest.find('-') {
+        Some(dash) => (&rest[..dash], &rest[dash + 1..]),
+        None => ("*", rest),
+    };
+
+    // Two digit years are read like systemd does, counting from 1970.
+    let year = match year.len() {
+        2 if year.chars().all(|x| x.is_ascii_digit()) => {
+            let short: u32 = year.parse().unwrap();
+            let century = if short < 70 { 2000 } else { 1900 };
+            Field::parse(&(century + short).to_string(), "year", MIN_YEAR, MAX_YEAR)?
+        }
+        _ => Field::parse(year, "year", MIN_YEAR, MAX_YEAR)?,
+    };
+    Ok((
+        year,
+        Field::parse(month, "month", 1, 12)?,
+        Field::parse(day, "day", 1, 31)?,
+        end_of_month,
+    ))
+}
+
+/// Parses a time such as `12:00`, `*:0/15` or `08..18:30:00`, where the seconds may be left out.
+fn parse_time(input: &str) -> Result<(Field, Field, Field), String> {
+    let parts: Vec<&str> = input.split(':').collect();
+    let (hour, minute, second) = match parts.as_slice() {
+        [hour, minute] => (*hour, *minute, "0"),
+        [hour, minute, second] => (*hour, *minute, *second),
+        _ => return Err(format!("Invalid time: {:?}", input)),
+    };
+    Ok((
+        Field::parse(hour, "hour", 0, 23)?,
+        Field::parse(minute, "minute", 0, 59)?,
+        Field::parse(second, "second", 0, 59)?,
+    ))
+}
+
+/// Parses `@` followed by the seconds since the epoch, which matches that moment once.
+fn parse_timestamp(input: &str) -> Result<CalendarSpec, String> {
+    let seconds: i64 = input
+        .parse()
+        .map_err(|_| format!("Invalid timestamp: {:?}", input))?;
+    let days = seconds.div_euclid(86400);
+    let time = seconds.rem_euclid(86400) as u32;
+    let (year, month, day) = civil_from_days(days);
+    Ok(CalendarSpec {
+        weekdays: 0,
+        year: Field::exact(year),
+        month: Field::exact(month),
+        day: Field::exact(day),
+        end_of_month: false,
+        hour: Field::exact(time / 3600),
+        minute: Field::exact(time / 60 % 60),
+        second: Field::exact(time % 60),
+        utc: true,
+    })
+}
+
+impl CalendarSpec {
+    /// Writes the expression in the normalized form shown by `systemd-analyze calendar`, such
+    /// as `Mon..Fri *-*-* 09:00:00`.
+    pub fn normalized(&self) -> String {
+        let mut output = String::new();
+        if self.weekdays != 0 {
+            output.push_str(&format_weekdays(self.weekdays));
+            output.push(' ');
+        }
+        output.push_str(&format!(
+            "{}-{}{}{} {}:{}:{}",
+            self.year.format(4),
+            self.month.format(2),
+            if self.end_of_month { '~' } else { '-' },
+            self.day.format(2),
+            self.hour.format(2),
+            self.minute.format(2),
+            self.second.format(2)
+        ));
+        if self.utc {
+            output.push_str(" UTC");
+        }
+        output
+    }
+
+    /// Whether the event happens on the given date.
+    fn matches_date(&self, year: u32, month: u32, day: u32) -> bool {
+        let length = days_in_month(year, month);
+        let day_matches = if self.end_of_month {
+            self.day.matches(length - day + 1)
+        } else {
+            self.day.matches(day)
+        };
+        let weekday = (days_from_civil(year, month, day) + 3).rem_euclid(7);
+        day_matches && (self.weekdays == 0 || self.weekdays & (1 << weekday) != 0)
+    }
+
+    /// Finds when the event next happens after the given time, in microseconds since the
+    /// epoch, or `None` if it never happens again.
+    pub fn next_elapse(&self, after: u64) -> Option<u64> {
+        let after = (after / 1_000_000) as i64;
+        let ((mut year, mut month, mut day), start) = self.broken_down(after)?;
+        let mut first = true;
+        let next_month = |year: &mut u32, month: &mut u32, day: &mut u32| {
+            *day = 1;
+            *month += 1;
+            if *month > 12 {
+                *month = 1;
+                *year += 1;
+            }
+        };
+
+        while year <= MAX_YEAR {
+            if !self.year.matches(year) {
+                year += 1;
+                month = 1;
+                day = 1;
+                first = false;
+                continue;
+            }
+            if !self.month.matches(month) || day > days_in_month(year, month) {
+                next_month(&mut year, &mut month, &mut day);
+                first = false;
+                continue;
+            }
+
+            if self.matches_date(year, month, day) {
+                for hour in (0..24).filter(|&x| self.hour.matches(x)) {
+                    for minute in (0..60).filter(|&x| self.minute.matches(x)) {
+                        for second in (0..60).filter(|&x| self.second.matches(x)) {
+                            if first && (hour, minute, second) <= start {
+                                continue;
+                            }
+                            let time = self.to_epoch(year, month, day, hour, minute, second)?;
+                            if time > after {
+                                return Some(time as u64 * 1_000_000);
+                            }
+                        }
+                    }
+                }
+            }
+            day += 1;
+            first = false;
+        }
+        None
+    }
+
+    /// The single moment a fully written timestamp such as `2024-01-31 12:00:00` stands for, in
+    /// microseconds since the epoch, or `None` if the expression matches more than one moment.
+    pub fn timestamp(&self) -> Option<u64> {
+        let fields = [
+            &self.year,
+            &self.month,
+            &self.day,
+            &self.hour,
+            &self.minute,
+            &self.second,
+        ];
+        if self.end_of_month || !fields.iter().all(|x| x.is_exact()) {
+            return None;
+        }
+        self.next_elapse(0)
+    }
+
+    /// Lists the next elapses of the event after the given time, like
+    /// `systemd-analyze calendar --iterations`.
+    pub fn next_elapses(&self, after: u64, count: usize) -> Vec<u64> {
+        let mut elapses = Vec::with_capacity(count);
+        let mut current = after;
+        while elapses.len() < count {
+            match self.next_elapse(current) {
+                Some(elapse) => {
+                    elapses.push(elapse);
+                    current = elapse;
+                }
+                None => break,
+            }
+        }
+        elapses
+    }
+
+    /// Splits seconds since the epoch into the date and time of the time zone of the event.
+    fn broken_down(&self, time: i64) -> Option<(Date, Time)> {
+        if self.utc {
+            let seconds = time.rem_euclid(86400) as u32;
+            return Some((
+                civil_from_days(time.div_euclid(86400)),
+                (seconds / 3600, seconds / 60 % 60, seconds % 60),
+            ));
+        }
+        let time = time as libc::time_t;
+        let mut tm: libc::tm = unsafe { ::std::mem::zeroed() };
+        if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+            return None;
+        }
+        Some((
+            (
+                (tm.tm_year + 1900) as u32,
+                (tm.tm_mon + 1) as u32,
+                tm.tm_mday as u32,
+            ),
+            (tm.tm_hour as u32, tm.tm_min as u32, tm.tm_sec as u32),
+        ))
+    }
+
+    /// Converts a date and time of the time zone of the event into seconds since the epoch.
+    /// Local times skipped by a daylight saving change move forward, as `mktime` does.
+    fn to_epoch(
+        &self,
+        year: u32,
+        month: u32,
+        day: u32,
+        hour: u32,
+        minute: u32,
+        second: u32,
+    ) -> Option<i64> {
+        if self.utc {
+            let days = days_from_civil(year, month, day);
+            return Some(days * 86400 + i64::from(hour * 3600 + minute * 60 + second));
+        }
+        let mut tm: libc::tm = unsafe { ::std::mem::zeroed() };
+        tm.tm_year = year as i32 - 1900;
+        tm.tm_mon = month as i32 - 1;
+        tm.tm_mday = day as i32;
+        tm.tm_hour = hour as i32;
+        tm.tm_min = minute as i32;
+        tm.tm_sec = second as i32;
+        tm.tm_isdst = -1;
+        match unsafe { libc::mktime(&mut tm) } {
+            -1 => None,
+            // `time_t` is narrower than 64 bits on some systems.
+            #[allow(clippy::unnecessary_cast)]
+            time => Some(time as i64),
+        }
+    }
+}
+
+/// Writes the days of the week, joining three or more consecutive days into a range.
+fn format_weekdays(weekdays: u8) -> String {
+    let mut items = Vec::new();
+    let mut day = 0;
+    while day < 7 {
+        if weekdays & (1 << day) == 0 {
+            day += 1;
+            continue;
+        }
+        let start = day;
+        while day + 1 < 7 && weekdays & (1 << (day + 1)) != 0 {
+            day += 1;
+        }
+        let short = |day: usize| &WEEKDAYS[day][..3];
+        match day - start {
+            0 => items.push(short(start).to_owned()),
+            1 => {
+                items.push(short(start).to_owned());
+                items.push(short(day).to_owned());
+            }
+            _ => items.push(format!("{}..{}", short(start), short(day))),
+        }
+        day += 1;
+    }
+    items.join(",")
+}
+
+fn is_leap_year(year: u32) -> bool {
+    (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// The number of days between the epoch and the given date.
+fn days_from_civil(year: u32, month: u32, day: u32) -> i64 {
+    // Counts years from March, so that the leap day is the last day of the year.
+    let year = i64::from(year) - if month <= 2 { 1 } else { 0 };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The date found the given number of days after the epoch.
+fn civil_from_days(days: i64) -> Date {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year as u32, month as u32, day as u32)
+}
+
+/// Finds the `OnCalendar=` settings of a timer unit file, as the index of their line and their
+/// expression. Empty settings, which only reset the earlier ones, are left out.
+pub fn unit_file_expressions(unit_file: &str) -> Vec<(usize, String)> {
+    unit_file
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let mut setting = line.splitn(2, '=');
+            match (setting.next(), setting.next()) {
+                (Some(key), Some(value))
+                    if key.trim() == "OnCalendar" && !value.trim().is_empty() =>
+                {
+                    Some((index, value.trim().to_owned()))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Replaces the `OnCalendar=` setting on the given line of a timer unit file. Without a line, a
+/// new setting is added at the start of the `[Timer]` section, which is created if needed.
+pub fn set_unit_file_expression(unit_file: &str, line: Option<usize>, expression: &str) -> String {
+    let setting = format!("OnCalendar={}", expression);
+    let mut lines: Vec<&str> = unit_file.lines().collect();
+    match line {
+        Some(line) if line < lines.len() => lines[line] = &setting,
+        _ => match lines.iter().position(|x| x.trim() == "[Timer]") {
+            Some(section) => lines.insert(section + 1, &setting),
+            None => {
+                lines.push("");
+                lines.push("[Timer]");
+                lines.push(&setting);
+            }
+        },
+    }
+    let mut output = lines.join("\n");
+    output.push('\n');
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(input: &str) -> String {
+        parse(input).unwrap().normalized()
+    }
+
+    /// Microseconds since the epoch of a date and time in UTC.
+    fn utc(year: u32, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> u64 {
+        let days = days_from_civil(year, month, day) as u64;
+        (days * 86400 + u64::from(hour * 3600 + minute * 60 + second)) * 1_000_000
+    }
+
+    #[test]
+    fn normalizes_shorthands() {
+        assert_eq!(normalize("minutely"), "*-*-* *:*:00");
+        assert_eq!(normalize("hourly"), "*-*-* *:00:00");
+        assert_eq!(normalize("daily"), "*-*-* 00:00:00");
+        assert_eq!(normalize("weekly"), "Mon *-*-* 00:00:00");
+        assert_eq!(normalize("monthly"), "*-*-01 00:00:00");
+        assert_eq!(normalize("quarterly"), "*-01,04,07,10-01 00:00:00");
+        assert_eq!(normalize("daily UTC"), "*-*-* 00:00:00 UTC");
+    }
+
+    #[test]
+    fn normalizes_expressions() {
+        assert_eq!(
+            normalize("Sat,Thu,Mon..Wed,Sat..Sun"),
+            "Mon..Thu,Sat,Sun *-*-* 00:00:00"
+        );
+        assert_eq!(
+            normalize("Mon,Sun 12-*-* 2,1:23"),
+            "Mon,Sun 2012-*-* 01,02:23:00"
+        );
+        assert_eq!(normalize("Wed *-1"), "Wed *-*-01 00:00:00");
+        assert_eq!(normalize("Wed-Wed,Wed *-1"), "Wed *-*-01 00:00:00");
+        assert_eq!(normalize("Wed, 17:48"), "Wed *-*-* 17:48:00");
+        assert_eq!(normalize("*:0/15"), "*-*-* *:00/15:00");
+        assert_eq!(normalize("*:*/15"), "*-*-* *:00/15:00");
+        assert_eq!(normalize("2003-03-05"), "2003-03-05 00:00:00");
+        assert_eq!(normalize("05:40"), "*-*-* 05:40:00");
+        assert_eq!(normalize("08..18/2:00"), "*-*-* 08..18/2:00:00");
+        assert_eq!(normalize("*-02~03"), "*-02~03 00:00:00");
+        assert_eq!(normalize("@1395716396"), "2014-03-25 02:59:56 UTC");
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        assert!(parse("").is_err());
+        assert!(parse("Funday").is_err());
+        assert!(parse("*-13-01").is_err());
+        assert!(parse("*-*-32").is_err());
+        assert!(parse("25:00").is_err());
+        assert!(parse("*:60").is_err());
+        assert!(parse("10..5:00").is_err());
+        assert!(parse("*:0/0").is_err());
+        assert!(parse("daily weekly").is_err());
+    }
+
+    #[test]
+    fn tells_valid_expressions_which_cannot_be_evaluated() {
+        assert!(unsupported("*:*:0/0.5").is_some());
+        assert!(unsupported("*-*-* 12:00:05.250").is_some());
+        assert!(unsupported("*-*-* 12:00:61.5").is_none());
+        assert!(unsupported("*-*-* 12:00").is_none());
+        assert!(unsupported("*-*-* 12:00 ../etc/passwd").is_none());
+        assert!(unsupported("*-*-* 12:00 Nowhere/Atlantis").is_none());
+        // Time zones can only be recognized where they are installed.
+        if Path::new(ZONEINFO).join("Europe/Berlin").is_file() {
+            assert!(unsupported("*-*-* 12:00 Europe/Berlin").is_some());
+            assert!(unsupported("*-*-* 25:00 Europe/Berlin").is_none());
+        }
+    }
+
+    #[test]
+    fn finds_the_next_elapses() {
+        let now = utc(2024, 2, 27, 13, 30, 0);
+        let spec = parse("daily UTC").unwrap();
+        assert_eq!(spec.next_elapse(now), Some(utc(2024, 2, 28, 0, 0, 0)));
+        let spec = parse("*:0/15 UTC").unwrap();
+        assert_eq!(
+            spec.next_elapses(now, 3),
+            vec![
+                utc(2024, 2, 27, 13, 45, 0),
+                utc(2024, 2, 27, 14, 0, 0),
+                utc(2024, 2, 27, 14, 15, 0),
+            ]
+        );
+        let spec = parse("Mon *-*-* 09:00 UTC").unwrap();
+        assert_eq!(spec.next_elapse(now), Some(utc(2024, 3, 4, 9, 0, 0)));
+        let spec = parse("*-02~01 UTC").unwrap();
+        assert_eq!(spec.next_elapse(now), Some(utc(2024, 2, 29, 0, 0, 0)));
+        let spec = parse("*-02-29 UTC").unwrap();
+        assert_eq!(
+            spec.next_elapse(utc(2024, 3, 1, 0, 0, 0)),
+            Some(utc(2028, 2, 29, 0, 0, 0))
+        );
+        let spec = parse("2001-01-01 UTC").unwrap();
+        assert_eq!(spec.next_elapse(now), None);
+    }
+}
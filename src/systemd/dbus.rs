@@ -1,5 +1,7 @@
 extern crate dbus;
-use std::{path::Path, sync::Mutex};
+use std::{collections::BTreeMap, path::Path, sync::Mutex};
+use systemd::processes::Process;
+use systemd::transient::{TransientKind, TransientUnit, Trigger};
 
 /// Whether to print debug messages in DbusHandle::send.
 const SEND_DEBUG: bool = false;
@@ -12,15 +14,173 @@ macro_rules! dbus_message {
         let interface = "org.freedesktop.systemd1.Manager";
         dbus::Message::new_method_call(dest, node, interface, $function).unwrap()
     }};
+    ($node:expr, $interface:expr, $function:expr) => {{
+        let dest = "org.freedesktop.systemd1";
+        dbus::Message::new_method_call(dest, $node, $interface, $function).unwrap()
+    }};
+}
+
+/// Returns the dbus object path under which systemd exposes the given unit name. Every byte
+/// that isn't alphanumeric is escaped as `_xx`, where `xx` is its hexadecimal value.
+pub fn unit_object_path(unit: &str) -> String {
+    let mut path = String::from("/org/freedesktop/systemd1/unit/");
+    for (index, byte) in unit.bytes().enumerate() {
+        if byte.is_ascii_alphabetic() || (index > 0 && byte.is_ascii_digit()) {
+            path.push(byte as char);
+        } else {
+            path.push_str(&format!("_{:02x}", byte));
+        }
+    }
+    path
+}
+
+/// The properties of a systemd dbus object, as returned by `org.freedesktop.DBus.Properties.GetAll`.
+#[derive(Clone, Debug, Default)]
+pub struct Properties(BTreeMap<String, dbus::MessageItem>);
+impl Properties {
+    /// Unpacks the `a{sv}` dictionary contained in the reply to a `GetAll` call.
+    fn from_reply(reply: &dbus::Message) -> Properties {
+        let mut properties = BTreeMap::new();
+        for item in reply.get_items() {
+            if let dbus::MessageItem::Array(entries, _) = item {
+                for entry in entries {
+                    if let dbus::MessageItem::DictEntry(key, value) = entry {
+                        if let dbus::MessageItem::Str(key) = *key {
+                            let value = match *value {
+                                dbus::MessageItem::Variant(value) => *value,
+                                value => value,
+                            };
+                            properties.insert(key, value);
+                        }
+                    }
+                }
+            }
+        }
+        Properties(properties)
+    }
+
+    /// Sets the value of a property, so that tests can build properties without a manager.
+    #[cfg(test)]
+    pub fn insert(&mut self, name: &str, value: dbus::MessageItem) {
+        self.0.insert(name.to_owned(), value);
+    }
+
+    /// Returns the raw value of a property.
+    pub fn get(&self, name: &str) -> Option<&dbus::MessageItem> {
+        self.0.get(name)
+    }
+
+    /// Returns the value of a string property.
+    pub fn string(&self, name: &str) -> Option<String> {
+        match self.get(name) {
+            Some(dbus::MessageItem::Str(value)) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of an unsigned integer property.
+    pub fn u64(&self, name: &str) -> Option<u64> {
+        match self.get(name) {
+            Some(&dbus::MessageItem::UInt64(value)) => Some(value),
+            Some(&dbus::MessageItem::UInt32(value)) => Some(value as u64),
+            Some(&dbus::MessageItem::UInt16(value)) => Some(value as u64),
+            Some(&dbus::MessageItem::Byte(value)) => Some(value as u64),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a resource counter, such as `MemoryCurrent`. Counters which aren't
+    /// being tracked are reported by systemd as the maximum value, and returned as `None`.
+    pub fn counter(&self, name: &str) -> Option<u64> {
+        self.u64(name).filter(|&value| value != u64::MAX)
+    }
+
+    /// Returns the value of a signed integer property.
+    pub fn i64(&self, name: &str) -> Option<i64> {
+        match self.get(name) {
+            Some(&dbus::MessageItem::Int64(value)) => Some(value),
+            Some(&dbus::MessageItem::Int32(value)) => Some(value as i64),
+            Some(&dbus::MessageItem::Int16(value)) => Some(value as i64),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a boolean property.
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn bool(&self, name: &str) -> Option<bool> {
+        match self.get(name) {
+            Some(&dbus::MessageItem::Bool(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a `(bas)` property, such as `SystemCallFilter`, which lists items
+    /// along with whether the list is an allow list rather than a deny list.
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn list(&self, name: &str) -> Option<(bool, Vec<String>)> {
+        match self.get(name) {
+            Some(dbus::MessageItem::Struct(fields)) => match fields.as_slice() {
+                [dbus::MessageItem::Bool(allow), dbus::MessageItem::Array(items, _)] => {
+                    let items = items
+                        .iter()
+                        .filter_map(|item| match item {
+                            dbus::MessageItem::Str(value) => Some(value.clone()),
+                            _ => None,
+                        })
+                        .collect();
+                    Some((*allow, items))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the values of a string array property, or an empty vector if it isn't set.
+    pub fn strings(&self, name: &str) -> Vec<String> {
+        match self.get(name) {
+            Some(dbus::MessageItem::Array(items, _)) => items
+                .iter()
+                .filter_map(|item| match *item {
+                    dbus::MessageItem::Str(ref value) => Some(value.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the values of an `a(ss)` property, such as the type and address of every socket
+    /// in `Listen`, or an empty vector if it isn't set.
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn string_pairs(&self, name: &str) -> Vec<(String, String)> {
+        match self.get(name) {
+            Some(dbus::MessageItem::Array(items, _)) => items
+                .iter()
+                .filter_map(|item| match *item {
+                    dbus::MessageItem::Struct(ref fields) => match fields.as_slice() {
+                        [dbus::MessageItem::Str(first), dbus::MessageItem::Str(second)] => {
+                            Some((first.clone(), second.clone()))
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct SystemdUnit {
     pub name: String,
     pub state: UnitState,
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
     pub utype: UnitType,
 }
 
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
 #[derive(Clone, PartialEq, Eq)]
 pub enum UnitType {
     Automount,
@@ -87,6 +247,22 @@ impl UnitState {
             _ => panic!("Unknown State: {}", x),
         }
     }
+
+    /// The name systemd gives to the state, as `systemctl list-unit-files` shows it.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            UnitState::Bad => "bad",
+            UnitState::Disabled => "disabled",
+            UnitState::Enabled => "enabled",
+            UnitState::Indirect => "indirect",
+            UnitState::Linked => "linked",
+            UnitState::Masked => "masked",
+            UnitState::Static => "static",
+            UnitState::Generated => "generated",
+            UnitState::Alias => "alias",
+            UnitState::Transient => "transient",
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -95,6 +271,7 @@ pub struct DbusHandle {
     connection: Mutex<Option<dbus::Connection>>,
 }
 impl DbusHandle {
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
     pub fn new(bus_type: dbus::BusType) -> Self {
         Self {
             bus_type,
@@ -102,6 +279,16 @@ impl DbusHandle {
         }
     }
 
+    /// Establishes the connection to the bus right away, so that a missing bus is reported as an
+    /// error rather than a panic on the first call.
+    pub fn try_new(bus_type: dbus::BusType) -> Result<Self, dbus::Error> {
+        let connection = dbus::Connection::get_private(bus_type)?;
+        Ok(Self {
+            bus_type,
+            connection: Some(connection).into(),
+        })
+    }
+
     /// Obtain a reference to the dbus::Connection, establishing it if necessary.
     pub fn con(&self) -> std::sync::MutexGuard<'_, Option<dbus::Connection>> {
         let mut conn = self.connection.lock().unwrap();
@@ -116,7 +303,7 @@ impl DbusHandle {
     /// Sends a dbus message and waits for a reply.
     pub fn send(&self, message: dbus::Message) -> Result<dbus::Message, dbus::Error> {
         if SEND_DEBUG {
-            println!(
+            eprintln!(
                 "Sending message {:?} from thread {:?}",
                 message,
                 std::thread::current().id()
@@ -131,19 +318,88 @@ impl DbusHandle {
     pub fn call(&self, function_name: &str) -> Result<dbus::Message, dbus::Error> {
         self.send(dbus_message!(function_name))
     }
+    /// Obtains every property of the given unit name for the dbus interface, such as
+    /// `org.freedesktop.systemd1.Service`. An empty interface returns the properties of all of
+    /// the interfaces implemented by the unit.
+    pub fn get_unit_properties(
+        &self,
+        unit: &str,
+        interface: &str,
+    ) -> Result<Properties, dbus::Error> {
+        let mut message = dbus_message!(
+            &unit_object_path(unit),
+            "org.freedesktop.DBus.Properties",
+            "GetAll"
+        );
+        message.append_items(&[interface.into()]);
+        self.send(message)
+            .map(|reply| Properties::from_reply(&reply))
+    }
+
+    /// Obtains every property of the service manager itself, such as the timestamps recorded
+    /// while booting.
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn get_manager_properties(&self) -> Result<Properties, dbus::Error> {
+        let mut message = dbus_message!(
+            "/org/freedesktop/systemd1",
+            "org.freedesktop.DBus.Properties",
+            "GetAll"
+        );
+        message.append_items(&["org.freedesktop.systemd1.Manager".into()]);
+        self.send(message)
+            .map(|reply| Properties::from_reply(&reply))
+    }
+
+    /// Obtains the list of units which are currently loaded by systemd.
+    pub fn list_units(&self) -> Result<Vec<LoadedUnit>, dbus::Error> {
+        self.call("ListUnits")
+            .map(|reply| parse_loaded_units(reply.get_items()))
+    }
+
+    /// Obtains every process within the control group of the given unit name.
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn get_unit_processes(&self, unit: &str) -> Result<Vec<Process>, dbus::Error> {
+        let mut message = dbus_message!("GetUnitProcesses");
+        message.append_items(&[unit.into()]);
+        let reply = self.send(message)?;
+
+        let mut processes = Vec::new();
+        for item in reply.get_items() {
+            if let dbus::MessageItem::Array(structs, _) = item {
+                for process in structs {
+                    if let dbus::MessageItem::Struct(fields) = process {
+                        if let [dbus::MessageItem::Str(cgroup), dbus::MessageItem::UInt32(pid), dbus::MessageItem::Str(command)] =
+                            &fields[..]
+                        {
+                            processes.push(Process::new(cgroup.clone(), *pid, command.clone()));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(processes)
+    }
+
     /// Communicates with dbus to obtain a list of unit files and returns them as a `Vec<SystemdUnit>`.
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
     pub fn list_unit_files(&self) -> Vec<SystemdUnit> {
-        let message = self.call("ListUnitFiles").unwrap().get_items();
-        parse_units_from_message(&format!("{:?}", message))
+        self.try_list_unit_files().unwrap()
+    }
+
+    /// Like `list_unit_files`, but returns the error of the call instead of panicking.
+    pub fn try_list_unit_files(&self) -> Result<Vec<SystemdUnit>, dbus::Error> {
+        self.call("ListUnitFiles")
+            .map(|reply| parse_units_from_message(&format!("{:?}", reply.get_items())))
     }
 
     /// Returns the current enablement status of the unit. Should be called with a unit name, not a path.
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
     pub fn get_unit_file_state(&self, name: &str) -> bool {
         //GetUnitFileState(in  s file, out s state);
         let mut msg = dbus_message!("GetUnitFileState");
         let unitname = if name.contains('/') {
             let stripped = name.split("/").last().unwrap();
-            println!("Warning: instead of a name, a path {name:?} was passed to get_unit_file_state. Stripping it to {stripped:?}");
+            eprintln!("Warning: instead of a name, a path {name:?} was passed to get_unit_file_state. Stripping it to {stripped:?}");
             stripped
         } else {
             name
@@ -164,15 +420,15 @@ impl DbusHandle {
         match self.send(message) {
             Ok(reply) => {
                 if format!("{:?}", reply.get_items()) == "[Bool(true), Array([], \"(sss)\")]" {
-                    println!("{} already enabled", unit);
+                    eprintln!("{} already enabled", unit);
                 } else {
-                    println!("{} has been enabled", unit);
+                    eprintln!("{} has been enabled", unit);
                 }
                 None
             }
             Err(reply) => {
                 let error = format!("Error enabling {}:\n{:?}", unit, reply);
-                println!("{}", error);
+                eprintln!("{}", error);
                 Some(error)
             }
         }
@@ -186,15 +442,15 @@ impl DbusHandle {
         match self.send(message) {
             Ok(reply) => {
                 if format!("{:?}", reply.get_items()) == "[Array([], \"(sss)\")]" {
-                    println!("{} is already disabled", unit);
+                    eprintln!("{} is already disabled", unit);
                 } else {
-                    println!("{} has been disabled", unit);
+                    eprintln!("{} has been disabled", unit);
                 }
                 None
             }
             Err(reply) => {
                 let error = format!("Error disabling {}:\n{:?}", unit, reply);
-                println!("{}", error);
+                eprintln!("{}", error);
                 Some(error)
             }
         }
@@ -206,12 +462,12 @@ impl DbusHandle {
         message.append_items(&[unit.into(), "fail".into()]);
         match self.send(message) {
             Ok(_) => {
-                println!("{} successfully started", unit);
+                eprintln!("{} successfully started", unit);
                 None
             }
             Err(error) => {
                 let output = format!("{} failed to start:\n{:?}", unit, error);
-                println!("{}", output);
+                eprintln!("{}", output);
                 Some(output)
             }
         }
@@ -223,16 +479,308 @@ impl DbusHandle {
         message.append_items(&[unit.into(), "fail".into()]);
         match self.send(message) {
             Ok(_) => {
-                println!("{} successfully stopped", unit);
+                eprintln!("{} successfully stopped", unit);
                 None
             }
             Err(error) => {
                 let output = format!("{} failed to stop:\n{:?}", unit, error);
-                println!("{}", output);
+                eprintln!("{}", output);
                 Some(output)
             }
         }
     }
+
+    /// Takes a unit name as input and attempts to restart it.
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn restart_unit(&self, unit: &str) -> Option<String> {
+        let mut message = dbus_message!("RestartUnit");
+        message.append_items(&[unit.into(), "fail".into()]);
+        match self.send(message) {
+            Ok(_) => {
+                eprintln!("{} successfully restarted", unit);
+                None
+            }
+            Err(error) => {
+                let output = format!("{} failed to restart:\n{:?}", unit, error);
+                eprintln!("{}", output);
+                Some(output)
+            }
+        }
+    }
+
+    /// Changes the given unsigned integer properties of a unit, such as `MemoryMax`. Runtime
+    /// changes are lost on reboot, while persistent ones are written to a drop-in file.
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn set_unit_properties(
+        &self,
+        unit: &str,
+        runtime: bool,
+        properties: &[(&str, u64)],
+    ) -> Option<String> {
+        let properties = properties
+            .iter()
+            .map(|&(name, value)| property(name, value.into()))
+            .collect();
+        let mut message = dbus_message!("SetUnitProperties");
+        message.append_items(&[
+            unit.into(),
+            runtime.into(),
+            dbus::MessageItem::Array(properties, "(sv)".into()),
+        ]);
+        match self.send(message) {
+            Ok(_) => {
+                eprintln!("The properties of {} have been changed", unit);
+                None
+            }
+            Err(error) => {
+                let output = format!("Error changing the properties of {}:\n{:?}", unit, error);
+                eprintln!("{}", output);
+                Some(output)
+            }
+        }
+    }
+
+    /// Starts the command described by the transient unit, like `systemd-run` does. Services
+    /// are started by systemd, or by a transient timer if a trigger was requested. For scopes,
+    /// the PID of the process which was already started must be given.
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn start_transient_unit(&self, unit: &TransientUnit, pid: Option<u32>) -> Option<String> {
+        let mut properties = vec![property("Description", unit.command.join(" ").into())];
+        for &(name, value) in &unit.limits {
+            properties.push(property(name, value.into()));
+        }
+
+        match unit.kind {
+            TransientKind::Service => {
+                let arguments = unit.command.iter().map(|x| x.as_str().into()).collect();
+                let exec_start = dbus::MessageItem::Struct(vec![
+                    unit.command[0].as_str().into(),
+                    dbus::MessageItem::Array(arguments, "s".into()),
+                    false.into(),
+                ]);
+                properties.push(property(
+                    "ExecStart",
+                    dbus::MessageItem::Array(vec![exec_start], "(sasb)".into()),
+                ));
+                if !unit.environment.is_empty() {
+                    let environment = unit.environment.iter().map(|x| x.as_str().into()).collect();
+                    properties.push(property(
+                        "Environment",
+                        dbus::MessageItem::Array(environment, "s".into()),
+                    ));
+                }
+                if let Some(ref directory) = unit.working_directory {
+                    properties.push(property("WorkingDirectory", directory.as_str().into()));
+                }
+                if let Some(ref user) = unit.user {
+                    properties.push(property("User", user.as_str().into()));
+                }
+                if unit.remain_after_exit {
+                    properties.push(property("RemainAfterExit", true.into()));
+                }
+            }
+            TransientKind::Scope => {
+                let pids = pid.into_iter().map(|x| x.into()).collect();
+                properties.push(property("PIDs", dbus::MessageItem::Array(pids, "u".into())));
+            }
+        }
+
+        // With a trigger, the service becomes the auxiliary unit of the timer.
+        let (name, properties, auxiliary) = match (&unit.trigger, unit.timer_name()) {
+            (Some(trigger), Some(timer)) => {
+                let timer_property = match *trigger {
+                    Trigger::Calendar(ref expression) => property(
+                        "TimersCalendar",
+                        dbus::MessageItem::Array(
+                            vec![dbus::MessageItem::Struct(vec![
+                                "OnCalendar".into(),
+                                expression.as_str().into(),
+                            ])],
+                            "(ss)".into(),
+                        ),
+                    ),
+                    Trigger::Delay(usec) => property(
+                        "TimersMonotonic",
+                        dbus::MessageItem::Array(
+                            vec![dbus::MessageItem::Struct(vec![
+                                "OnActiveUSec".into(),
+                                usec.into(),
+                            ])],
+                            "(st)".into(),
+                        ),
+                    ),
+                };
+                let timer_properties = vec![
+                    property("Description", unit.command.join(" ").into()),
+                    timer_property,
+                    property("RemainAfterElapse", false.into()),
+                ];
+                let service = dbus::MessageItem::Struct(vec![
+                    unit.unit_name().into(),
+                    dbus::MessageItem::Array(properties, "(sv)".into()),
+                ]);
+                (timer, timer_properties, vec![service])
+            }
+            _ => (unit.unit_name(), properties, Vec::new()),
+        };
+
+        let mut message = dbus_message!("StartTransientUnit");
+        message.append_items(&[
+            name.as_str().into(),
+            "fail".into(),
+            dbus::MessageItem::Array(properties, "(sv)".into()),
+            dbus::MessageItem::Array(auxiliary, "(sa(sv))".into()),
+        ]);
+        match self.send(message) {
+            Ok(_) => {
+                eprintln!("{} successfully started", name);
+                None
+            }
+            Err(error) => {
+                let output = format!("{} failed to start:\n{:?}", name, error);
+                eprintln!("{}", output);
+                Some(output)
+            }
+        }
+    }
+
+    /// Reloads the configuration of every unit, so that new and changed unit files and
+    /// drop-ins take effect, like `systemctl daemon-reload`.
+    pub fn reload(&self) -> Option<String> {
+        match self.call("Reload") {
+            Ok(_) => {
+                eprintln!("The unit files have been reloaded");
+                None
+            }
+            Err(error) => {
+                let output = format!("Error reloading the unit files:\n{:?}", error);
+                eprintln!("{}", output);
+                Some(output)
+            }
+        }
+    }
+
+    /// Takes the unit pathname as input and masks it via dbus.
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub fn mask_unit_files(&self, unit: &str) -> Option<String> {
+        let mut message = dbus_message!("MaskUnitFiles");
+        message.append_items(&[[unit][..].into(), false.into(), false.into()]);
+        match self.send(message) {
+            Ok(_) => {
+                eprintln!("{} has been masked", unit);
+                None
+            }
+            Err(reply) => {
+                let error = format!("Error masking {}:\n{:?}", unit, reply);
+                eprintln!("{}", error);
+                Some(error)
+            }
+        }
+    }
+}
+
+/// An operation which can be applied to several units at once.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BatchAction {
+    Start,
+    Stop,
+    Restart,
+    Enable,
+    Disable,
+    Mask,
+}
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+impl BatchAction {
+    /// The verb describing this action in the interface.
+    pub fn label(self) -> &'static str {
+        match self {
+            BatchAction::Start => "Start",
+            BatchAction::Stop => "Stop",
+            BatchAction::Restart => "Restart",
+            BatchAction::Enable => "Enable",
+            BatchAction::Disable => "Disable",
+            BatchAction::Mask => "Mask",
+        }
+    }
+
+    /// Applies the action to the given unit name, returning the error message on failure.
+    pub fn apply(self, handle: &DbusHandle, unit: &str) -> Option<String> {
+        match self {
+            BatchAction::Start => handle.start_unit(unit),
+            BatchAction::Stop => handle.stop_unit(unit),
+            BatchAction::Restart => handle.restart_unit(unit),
+            BatchAction::Enable => handle.enable_unit_files(unit),
+            BatchAction::Disable => handle.disable_unit_files(unit),
+            BatchAction::Mask => handle.mask_unit_files(unit),
+        }
+    }
+
+    /// Whether a unit is enabled after the action succeeded, or `None` if the action doesn't
+    /// change it.
+    pub fn enablement(self) -> Option<bool> {
+        match self {
+            BatchAction::Enable => Some(true),
+            BatchAction::Disable | BatchAction::Mask => Some(false),
+            BatchAction::Start | BatchAction::Stop | BatchAction::Restart => None,
+        }
+    }
+
+    /// Whether a unit is active after the action succeeded, or `None` if the action doesn't
+    /// change it.
+    pub fn activity(self) -> Option<bool> {
+        match self {
+            BatchAction::Start | BatchAction::Restart => Some(true),
+            BatchAction::Stop => Some(false),
+            BatchAction::Enable | BatchAction::Disable | BatchAction::Mask => None,
+        }
+    }
+}
+
+/// Builds the `(sv)` struct used to pass a property to systemd.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+fn property(name: &str, value: dbus::MessageItem) -> dbus::MessageItem {
+    dbus::MessageItem::Struct(vec![
+        name.into(),
+        dbus::MessageItem::Variant(Box::new(value)),
+    ])
+}
+
+/// A unit which is currently loaded by systemd, as reported by `ListUnits`.
+#[derive(Clone, Debug)]
+pub struct LoadedUnit {
+    pub name: String,
+    pub description: String,
+    pub load_state: String,
+    pub active_state: String,
+    pub sub_state: String,
+}
+
+/// Takes the items of a `ListUnits` reply and maps each `(ssssssouso)` struct to a `LoadedUnit`.
+fn parse_loaded_units(items: Vec<dbus::MessageItem>) -> Vec<LoadedUnit> {
+    let mut units = Vec::new();
+    for item in items {
+        if let dbus::MessageItem::Array(structs, _) = item {
+            for unit in structs {
+                if let dbus::MessageItem::Struct(fields) = unit {
+                    let mut fields = fields.into_iter().map(|field| match field {
+                        dbus::MessageItem::Str(value) => value,
+                        _ => String::new(),
+                    });
+                    let mut next = || fields.next().unwrap_or_default();
+                    units.push(LoadedUnit {
+                        name: next(),
+                        description: next(),
+                        load_state: next(),
+                        active_state: next(),
+                        sub_state: next(),
+                    });
+                }
+            }
+        }
+    }
+    units.sort_by(|a, b| a.name.cmp(&b.name));
+    units
 }
 
 /// Takes the dbus message as input and maps the information to a `Vec<SystemdUnit>`.
@@ -261,6 +809,7 @@ fn parse_units_from_message(input: &str) -> Vec<SystemdUnit> {
 
 /// Takes a `Vec<SystemdUnit>` as input and returns a new vector only containing services which can be enabled and
 /// disabled.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
 pub fn collect_togglable_services(units: &[SystemdUnit]) -> Vec<SystemdUnit> {
     units
         .iter()
@@ -275,6 +824,7 @@ pub fn collect_togglable_services(units: &[SystemdUnit]) -> Vec<SystemdUnit> {
 
 /// Takes a `Vec<SystemdUnit>` as input and returns a new vector only containing sockets which can be enabled and
 /// disabled.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
 pub fn collect_togglable_sockets(units: &[SystemdUnit]) -> Vec<SystemdUnit> {
     units
         .iter()
@@ -286,8 +836,20 @@ pub fn collect_togglable_sockets(units: &[SystemdUnit]) -> Vec<SystemdUnit> {
         .collect()
 }
 
+/// Takes a `Vec<SystemdUnit>` as input and returns a new vector only containing transient units, such as the ones
+/// started by `systemd-run`.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub fn collect_transient_units(units: &[SystemdUnit]) -> Vec<SystemdUnit> {
+    units
+        .iter()
+        .filter(|x| x.state == UnitState::Transient)
+        .cloned()
+        .collect()
+}
+
 /// Takes a `Vec<SystemdUnit>` as input and returns a new vector only containing timers which can be enabled and
 /// disabled.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
 pub fn collect_togglable_timers(units: &[SystemdUnit]) -> Vec<SystemdUnit> {
     units
         .iter()
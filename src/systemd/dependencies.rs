@@ -0,0 +1,46 @@
+use systemd::dbus::Properties;
+
+/// Requirement dependencies, paired with the property listing the same relation in reverse.
+const REQUIREMENTS: [(&str, &str); 5] = [
+    ("Requires", "RequiredBy"),
+    ("Requisite", "RequisiteOf"),
+    ("Wants", "WantedBy"),
+    ("BindsTo", "BoundBy"),
+    ("PartOf", "ConsistsOf"),
+];
+
+/// Ordering dependencies, paired with the property listing the same relation in reverse.
+const ORDERING: [(&str, &str); 2] = [("After", "Before"), ("Before", "After")];
+
+/// A unit which another unit is related to, along with every relation between the two.
+#[derive(Clone, Debug)]
+pub struct Dependency {
+    pub unit: String,
+    pub relations: Vec<&'static str>,
+}
+
+/// Lists the units that the unit with the given properties depends on, sorted by name. In
+/// `reverse` mode, the units which depend on this unit are listed instead. Ordering
+/// dependencies are only included if `ordering` is set, as they relate to most of the system.
+pub fn dependencies(properties: &Properties, reverse: bool, ordering: bool) -> Vec<Dependency> {
+    let relations = REQUIREMENTS
+        .iter()
+        .chain(if ordering { &ORDERING[..] } else { &[] })
+        .map(|&(forward, backward)| if reverse { backward } else { forward });
+
+    let mut dependencies: Vec<Dependency> = Vec::new();
+    for relation in relations {
+        for unit in properties.strings(relation) {
+            match dependencies.iter().position(|x| x.unit == unit) {
+                Some(index) => dependencies[index].relations.push(relation),
+                None => dependencies.push(Dependency {
+                    unit,
+                    relations: vec![relation],
+                }),
+            }
+        }
+    }
+
+    dependencies.sort_by(|a, b| a.unit.cmp(&b.unit));
+    dependencies
+}
@@ -0,0 +1,343 @@
+use std::collections::VecDeque;
+use systemd::dbus::DbusHandle;
+use systemd::dependencies;
+
+/// Graphs larger than this stop growing, as they would be unreadable anyway.
+const MAX_NODES: usize = 200;
+
+/// Dimensions used when laying out the graph.
+const CHAR_WIDTH: f64 = 7.0;
+const NODE_PADDING: f64 = 10.0;
+const NODE_HEIGHT: f64 = 26.0;
+const HORIZONTAL_GAP: f64 = 20.0;
+const VERTICAL_GAP: f64 = 60.0;
+const MARGIN: f64 = 10.0;
+
+/// A unit in the graph, along with its distance from the root unit.
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub unit: String,
+    pub depth: usize,
+}
+
+/// A relation between two nodes of the graph, referenced by their index.
+#[derive(Clone, Debug)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub relation: &'static str,
+}
+
+/// The position and size of a node once the graph has been laid out.
+#[derive(Clone, Copy, Debug)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// The placement of every node of a graph, in the same order as the nodes.
+#[derive(Clone, Debug)]
+pub struct Layout {
+    pub nodes: Vec<Rect>,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// The requirement and ordering relations between a unit and the units it pulls in, similar
+/// to the output of `systemd-analyze dot`.
+#[derive(Clone, Debug, Default)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+impl Graph {
+    /// Follows the requirement dependencies of the root unit up to `max_depth` levels deep.
+    /// Ordering and conflict relations are then added between the units which were collected.
+    pub fn build(handle: &DbusHandle, root: &str, max_depth: usize) -> Result<Graph, String> {
+        let mut graph = Graph::default();
+        let mut properties = Vec::new();
+        let mut queue = VecDeque::new();
+        graph.nodes.push(Node {
+            unit: root.to_owned(),
+            depth: 0,
+        });
+        queue.push_back(0);
+
+        while let Some(index) = queue.pop_front() {
+            let unit = graph.nodes[index].unit.clone();
+            let depth = graph.nodes[index].depth;
+            let unit_properties = handle
+                .get_unit_properties(&unit, "org.freedesktop.systemd1.Unit")
+                .map_err(|error| {
+                    format!("Unable to get the properties of {}: {:?}", unit, error)
+                })?;
+
+            if depth < max_depth {
+                for dependency in dependencies::dependencies(&unit_properties, false, false) {
+                    let to = match graph.index_of(&dependency.unit) {
+                        Some(to) => to,
+                        None if graph.nodes.len() < MAX_NODES => {
+                            graph.nodes.push(Node {
+                                unit: dependency.unit.clone(),
+                                depth: depth + 1,
+                            });
+                            queue.push_back(graph.nodes.len() - 1);
+                            graph.nodes.len() - 1
+                        }
+                        None => continue,
+                    };
+                    for relation in dependency.relations {
+                        graph.edges.push(Edge {
+                            from: index,
+                            to,
+                            relation,
+                        });
+                    }
+                }
+            }
+            properties.push((index, unit_properties));
+        }
+
+        for (index, unit_properties) in properties {
+            for &relation in &["After", "Conflicts"] {
+                for unit in unit_properties.strings(relation) {
+                    if let Some(to) = graph.index_of(&unit) {
+                        graph.edges.push(Edge {
+                            from: index,
+                            to,
+                            relation,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(graph)
+    }
+
+    fn index_of(&self, unit: &str) -> Option<usize> {
+        self.nodes.iter().position(|x| x.unit == unit)
+    }
+
+    /// Renders the graph in the DOT language, using the colors of `systemd-analyze dot`.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph systemd {\n");
+        for node in &self.nodes {
+            dot.push_str(&format!("\t\"{}\";\n", escape_dot(&node.unit)));
+        }
+        for edge in &self.edges {
+            dot.push_str(&format!(
+                "\t\"{}\"->\"{}\" [color=\"{}\"];\n",
+                escape_dot(&self.nodes[edge.from].unit),
+                escape_dot(&self.nodes[edge.to].unit),
+                relation_color(edge.relation).0
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Places each node on the row matching its depth, with the rows centered horizontally.
+    pub fn layout(&self) -> Layout {
+        let max_depth = self.nodes.iter().map(|x| x.depth).max().unwrap_or(0);
+        let mut rows: Vec<Vec<usize>> = vec![Vec::new(); max_depth + 1];
+        for (index, node) in self.nodes.iter().enumerate() {
+            rows[node.depth].push(index);
+        }
+
+        let node_width = |index: usize| -> f64 {
+            self.nodes[index].unit.chars().count() as f64 * CHAR_WIDTH + 2.0 * NODE_PADDING
+        };
+        let row_width = |row: &[usize]| -> f64 {
+            row.iter()
+                .map(|&x| node_width(x) + HORIZONTAL_GAP)
+                .sum::<f64>()
+                - HORIZONTAL_GAP
+        };
+
+        let width = rows.iter().map(|x| row_width(x)).fold(0.0, f64::max) + 2.0 * MARGIN;
+        let height = rows.len() as f64 * (NODE_HEIGHT + VERTICAL_GAP) - VERTICAL_GAP + 2.0 * MARGIN;
+
+        let mut nodes = vec![
+            Rect {
+                x: 0.0,
+                y: 0.0,
+                width: 0.0,
+                height: 0.0,
+            };
+            self.nodes.len()
+        ];
+        for (depth, row) in rows.iter_mut().enumerate() {
+            row.sort_by(|&a, &b| self.nodes[a].unit.cmp(&self.nodes[b].unit));
+            let mut x = (width - row_width(row)) / 2.0;
+            let y = MARGIN + depth as f64 * (NODE_HEIGHT + VERTICAL_GAP);
+            for &index in row.iter() {
+                nodes[index] = Rect {
+                    x,
+                    y,
+                    width: node_width(index),
+                    height: NODE_HEIGHT,
+                };
+                x += node_width(index) + HORIZONTAL_GAP;
+            }
+        }
+
+        Layout {
+            nodes,
+            width,
+            height,
+        }
+    }
+
+    /// Renders the laid out graph as a standalone SVG document.
+    pub fn to_svg(&self) -> String {
+        let layout = self.layout();
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" \
+             viewBox=\"0 0 {0} {1}\" font-family=\"monospace\" font-size=\"12\">\n\
+             <rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n",
+            layout.width, layout.height
+        );
+
+        for edge in &self.edges {
+            let (from, to) = edge_endpoints(&layout.nodes[edge.from], &layout.nodes[edge.to]);
+            let color = relation_color(edge.relation).0;
+            svg.push_str(&format!(
+                "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\"/>\n",
+                from.0, from.1, to.0, to.1, color
+            ));
+            let (left, right) = arrow_head(from, to);
+            svg.push_str(&format!(
+                "<polygon points=\"{},{} {},{} {},{}\" fill=\"{}\"/>\n",
+                to.0, to.1, left.0, left.1, right.0, right.1, color
+            ));
+        }
+
+        for (node, rect) in self.nodes.iter().zip(layout.nodes.iter()) {
+            svg.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"4\" fill=\"white\" stroke=\"black\"/>\n\
+                 <text x=\"{}\" y=\"{}\">{}</text>\n",
+                rect.x,
+                rect.y,
+                rect.width,
+                rect.height,
+                rect.x + NODE_PADDING,
+                rect.y + rect.height / 2.0 + 4.0,
+                escape_xml(&node.unit)
+            ));
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+/// The color used to draw a relation, as a DOT/SVG color name and as an RGB triplet.
+pub fn relation_color(relation: &str) -> (&'static str, (f64, f64, f64)) {
+    match relation {
+        "Requires" | "BindsTo" => ("black", (0.0, 0.0, 0.0)),
+        "Requisite" => ("darkblue", (0.0, 0.0, 0.55)),
+        "Wants" | "PartOf" => ("grey66", (0.66, 0.66, 0.66)),
+        "Conflicts" => ("red", (1.0, 0.0, 0.0)),
+        "After" => ("green", (0.0, 0.5, 0.0)),
+        _ => ("black", (0.0, 0.0, 0.0)),
+    }
+}
+
+/// Returns the points where an edge between two nodes should start and end. Edges leave from
+/// the bottom of the upper node and enter the top of the lower one.
+pub fn edge_endpoints(from: &Rect, to: &Rect) -> ((f64, f64), (f64, f64)) {
+    let from_x = from.x + from.width / 2.0;
+    let to_x = to.x + to.width / 2.0;
+    if from.y < to.y {
+        ((from_x, from.y + from.height), (to_x, to.y))
+    } else if from.y > to.y {
+        ((from_x, from.y), (to_x, to.y + to.height))
+    } else {
+        let from_y = from.y + from.height / 2.0;
+        if from.x < to.x {
+            ((from.x + from.width, from_y), (to.x, from_y))
+        } else {
+            ((from.x, from_y), (to.x + to.width, from_y))
+        }
+    }
+}
+
+/// Returns the two base corners of the arrow head drawn at the end of an edge.
+pub fn arrow_head(from: (f64, f64), to: (f64, f64)) -> ((f64, f64), (f64, f64)) {
+    const LENGTH: f64 = 8.0;
+    const SPREAD: f64 = 0.4;
+    let angle = (to.1 - from.1).atan2(to.0 - from.0);
+    let corner = |offset: f64| {
+        (
+            to.0 - LENGTH * (angle + offset).cos(),
+            to.1 - LENGTH * (angle + offset).sin(),
+        )
+    };
+    (corner(SPREAD), corner(-SPREAD))
+}
+
+/// Escapes the characters which have a meaning in a quoted DOT identifier.
+pub fn escape_dot(input: &str) -> String {
+    input.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Escapes the characters which have a meaning in XML text and attribute values.
+pub fn escape_xml(input: &str) -> String {
+    input
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(units: &[&str]) -> Graph {
+        Graph {
+            nodes: units
+                .iter()
+                .map(|unit| Node {
+                    unit: unit.to_string(),
+                    depth: 0,
+                })
+                .collect(),
+            edges: vec![Edge {
+                from: 0,
+                to: 1,
+                relation: "Requires",
+            }],
+        }
+    }
+
+    #[test]
+    fn dot_escapes_quotes_and_backslashes() {
+        let dot = graph(&["a\\x2db.service", "say\"hi\".service"]).to_dot();
+        assert!(dot.contains("\t\"a\\\\x2db.service\";\n"));
+        assert!(dot.contains("\t\"say\\\"hi\\\".service\";\n"));
+        assert!(
+            dot.contains("\t\"a\\\\x2db.service\"->\"say\\\"hi\\\".service\" [color=\"black\"];\n")
+        );
+    }
+
+    #[test]
+    fn dot_leaves_plain_names_alone() {
+        assert_eq!(escape_dot("sshd.service"), "sshd.service");
+    }
+
+    #[test]
+    fn svg_escapes_markup_and_quotes() {
+        assert_eq!(
+            escape_xml("<a & \"b\">\\"),
+            "&lt;a &amp; &quot;b&quot;&gt;\\"
+        );
+        let svg = graph(&["a&b.service", "<c>.service"]).to_svg();
+        assert!(svg.contains(">a&amp;b.service</text>"));
+        assert!(svg.contains(">&lt;c&gt;.service</text>"));
+    }
+}
@@ -0,0 +1,272 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use systemd::analyze::{Analyze, BootTimes};
+
+/// The file the kernel exposes the ID of the current boot in.
+const BOOT_ID_PATH: &str = "/proc/sys/kernel/random/boot_id";
+
+/// Reads the ID of the current boot, written without dashes like the journal does.
+pub fn current_boot_id() -> io::Result<String> {
+    fs::read_to_string(BOOT_ID_PATH).map(|id| id.trim().replace('-', ""))
+}
+
+/// The timing of a single boot, as recorded in the history.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BootRecord {
+    pub id: String,
+    /// When the kernel started, in microseconds since the epoch.
+    pub started: u64,
+    /// The duration of each phase of the boot, in microseconds, in the order they happened.
+    pub phases: Vec<(String, u64)>,
+    /// The time each unit took to activate, in microseconds, as reported by
+    /// `systemd-analyze blame`.
+    pub units: Vec<(String, u64)>,
+}
+
+impl BootRecord {
+    /// Records the boot, dated from the start of the kernel. User managers don't know when the
+    /// kernel started, so their boots are dated from the time they were recorded instead.
+    pub fn new(id: String, times: &BootTimes, blame: &[Analyze]) -> BootRecord {
+        let now = || {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|x| x.as_micros() as u64)
+                .unwrap_or(0)
+        };
+        BootRecord {
+            id,
+            started: times.kernel_started.unwrap_or_else(now),
+            phases: times
+                .phases()
+                .into_iter()
+                .map(|(name, duration)| (name.to_owned(), duration))
+                .collect(),
+            units: blame
+                .iter()
+                .map(|x| (x.service.clone(), u64::from(x.time) * 1000))
+                .collect(),
+        }
+    }
+
+    /// The time from powering on the machine until the boot finished.
+    pub fn total(&self) -> u64 {
+        self.phases.iter().map(|&(_, duration)| duration).sum()
+    }
+
+    /// The duration of a phase of the boot, if it was recorded.
+    pub fn phase(&self, name: &str) -> Option<u64> {
+        self.phases
+            .iter()
+            .find(|(phase, _)| phase == name)
+            .map(|&(_, duration)| duration)
+    }
+
+    /// Writes the record as lines of space separated fields, such as `unit 1234 foo.service`.
+    /// Unit names are written last as they are the only field which may contain spaces.
+    pub fn serialize(&self) -> String {
+        let mut output = format!("boot {}\nstarted {}\n", self.id, self.started);
+        for (name, duration) in &self.phases {
+            output.push_str(&format!("phase {} {}\n", duration, name));
+        }
+        for (unit, duration) in &self.units {
+            output.push_str(&format!("unit {} {}\n", duration, unit));
+        }
+        output
+    }
+
+    /// Reads a record written by `serialize`. Lines which can't be understood are skipped, so
+    /// that records written by later versions can still be read.
+    pub fn parse(input: &str) -> Option<BootRecord> {
+        let mut record = BootRecord {
+            id: String::new(),
+            started: 0,
+            phases: Vec::new(),
+            units: Vec::new(),
+        };
+        for line in input.lines() {
+            let mut fields = line.splitn(3, ' ');
+            match (fields.next(), fields.next(), fields.next()) {
+                (Some("boot"), Some(id), None) => record.id = id.to_owned(),
+                (Some("started"), Some(started), None) => {
+                    if let Ok(started) = started.parse() {
+                        record.started = started;
+                    }
+                }
+                (Some("phase"), Some(duration), Some(name)) => {
+                    if let Ok(duration) = duration.parse() {
+                        record.phases.push((name.to_owned(), duration));
+                    }
+                }
+                (Some("unit"), Some(duration), Some(unit)) => {
+                    if let Ok(duration) = duration.parse() {
+                        record.units.push((unit.to_owned(), duration));
+                    }
+                }
+                _ => (),
+            }
+        }
+        if record.id.is_empty() {
+            None
+        } else {
+            Some(record)
+        }
+    }
+}
+
+/// The change in the activation time of a unit between two boots. Units which only took part in
+/// one of the boots have no time in the other.
+#[derive(Clone, Debug)]
+pub struct UnitDelta {
+    pub unit: String,
+    pub before: Option<u64>,
+    pub after: Option<u64>,
+}
+
+impl UnitDelta {
+    /// How much slower the unit became, in microseconds. Units which got faster are negative.
+    pub fn delta(&self) -> i64 {
+        self.after.unwrap_or(0) as i64 - self.before.unwrap_or(0) as i64
+    }
+}
+
+/// Pairs up the units of two boots, ordered from the unit which slowed down the most to the one
+/// which sped up the most.
+pub fn compare(before: &BootRecord, after: &BootRecord) -> Vec<UnitDelta> {
+    let mut deltas: Vec<UnitDelta> = before
+        .units
+        .iter()
+        .map(|(unit, duration)| UnitDelta {
+            unit: unit.clone(),
+            before: Some(*duration),
+            after: None,
+        })
+        .collect();
+    for (unit, duration) in &after.units {
+        match deltas.iter_mut().find(|x| &x.unit == unit) {
+            Some(delta) => delta.after = Some(*duration),
+            None => deltas.push(UnitDelta {
+                unit: unit.clone(),
+                before: None,
+                after: Some(*duration),
+            }),
+        }
+    }
+    deltas.sort_by(|a, b| b.delta().cmp(&a.delta()).then_with(|| a.unit.cmp(&b.unit)));
+    deltas
+}
+
+/// A directory holding a record of every boot seen so far, one file per boot ID. The system and
+/// user managers are recorded separately.
+#[derive(Clone, Debug)]
+pub struct BootHistory {
+    directory: PathBuf,
+}
+
+impl BootHistory {
+    /// Opens the history within `$XDG_DATA_HOME`, falling back to `~/.local/share`.
+    pub fn open(user: bool) -> BootHistory {
+        let data = env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .filter(|x| x.is_absolute())
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+            .unwrap_or_else(env::temp_dir);
+        let manager = if user { "user" } else { "system" };
+        BootHistory {
+            directory: data.join("systemd-manager").join("boots").join(manager),
+        }
+    }
+
+    /// Saves the record, replacing any earlier record of the same boot. The file is written
+    /// under a temporary name first, so that an interrupted write never leaves a partial record.
+    pub fn record(&self, record: &BootRecord) -> io::Result<()> {
+        fs::create_dir_all(&self.directory)?;
+        let path = self.directory.join(&record.id);
+        let temporary = self.directory.join(format!(".{}.tmp", record.id));
+        fs::write(&temporary, record.serialize())?;
+        fs::rename(&temporary, &path)
+    }
+
+    /// Reads every recorded boot, from the oldest to the most recent.
+    pub fn load(&self) -> Vec<BootRecord> {
+        let entries = match fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+        let mut records: Vec<BootRecord> = entries
+            .filter_map(Result::ok)
+            .filter(|x| !x.file_name().to_string_lossy().starts_with('.'))
+            .filter_map(|x| fs::read_to_string(x.path()).ok())
+            .filter_map(|x| BootRecord::parse(&x))
+            .collect();
+        records.sort_by_key(|x| x.started);
+        records
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(id: &str, units: &[(&str, u64)]) -> BootRecord {
+        BootRecord {
+            id: id.to_owned(),
+            started: 1_700_000_000_000_000,
+            phases: vec![
+                (String::from("Kernel"), 1_500_000),
+                (String::from("Userspace"), 4_250_000),
+            ],
+            units: units
+                .iter()
+                .map(|&(x, time)| (x.to_owned(), time))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn records_survive_serialization() {
+        let boot = record("abc", &[("foo.service", 1000), ("a b.mount", 250_000)]);
+        assert_eq!(BootRecord::parse(&boot.serialize()), Some(boot.clone()));
+
+        let mut input = boot.serialize();
+        input.push_str("unit soon foo.service\nphase x Initrd\nfuture field\n");
+        assert_eq!(BootRecord::parse(&input), Some(boot));
+        assert_eq!(BootRecord::parse("started 1\nunit 5 foo.service\n"), None);
+    }
+
+    #[test]
+    fn units_are_compared_from_the_most_slowed_down() {
+        let before = record(
+            "a",
+            &[
+                ("fast.service", 100),
+                ("slow.service", 100),
+                ("gone.service", 50),
+            ],
+        );
+        let after = record(
+            "b",
+            &[
+                ("slow.service", 900),
+                ("fast.service", 40),
+                ("new.service", 10),
+            ],
+        );
+        let deltas = compare(&before, &after);
+        let deltas: Vec<(&str, Option<u64>, Option<u64>, i64)> = deltas
+            .iter()
+            .map(|x| (x.unit.as_str(), x.before, x.after, x.delta()))
+            .collect();
+        assert_eq!(
+            deltas,
+            vec![
+                ("slow.service", Some(100), Some(900), 800),
+                ("new.service", None, Some(10), 10),
+                ("gone.service", Some(50), None, -50),
+                ("fast.service", Some(100), Some(40), -60),
+            ]
+        );
+    }
+}
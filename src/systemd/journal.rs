@@ -0,0 +1,1134 @@
+extern crate libc;
+extern crate regex;
+
+use self::libc::{c_char, c_int, c_void, size_t};
+use self::regex::{Regex, RegexBuilder};
+use std::cmp::Reverse;
+use std::ffi::{CStr, CString};
+use std::io;
+use std::ptr;
+use std::str;
+use systemd::calendar;
+use systemd::history;
+use systemd::json::json_string;
+use systemd::timespan;
+
+/// The journal as opened by `sd_journal_open`, only ever used behind a pointer.
+enum SdJournal {}
+
+/// Flags of `sd_journal_open`.
+const SD_JOURNAL_LOCAL_ONLY: c_int = 1;
+const SD_JOURNAL_SYSTEM: c_int = 4;
+const SD_JOURNAL_CURRENT_USER: c_int = 8;
+
+/// The names of the syslog priorities, from the most important.
+pub const PRIORITIES: [&str; 8] = [
+    "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+];
+
+/// The `MESSAGE_ID`s of the entries logged by the manager as a unit runs, which tell how each
+/// run ended.
+const UNIT_STOPPED_MESSAGE_ID: &str = "9d1aaa27d60140bd96365438aad20286";
+const UNIT_FAILED_MESSAGE_ID: &str = "be02cf6855d2428ba40df7e9d022f03d";
+const UNIT_SUCCESS_MESSAGE_ID: &str = "7ad2d189f7e94e70a38c781354912448";
+const UNIT_RESULT_MESSAGE_ID: &str = "d9b373ed55a64feb8242e02dbe79a49c";
+const UNIT_PROCESS_EXIT_MESSAGE_ID: &str = "98e322203f7a4ed290d09fe03c09fe15";
+
+/// The `MESSAGE_ID` of the entries logged by `systemd-coredump` when a process crashes.
+pub const COREDUMP_MESSAGE_ID: &str = "fc2e22bc6ee647b6b90729ab34a250b1";
+
+#[link(name = "systemd")]
+extern "C" {
+    fn sd_journal_open(ret: *mut *mut SdJournal, flags: c_int) -> c_int;
+    fn sd_journal_close(journal: *mut SdJournal);
+    fn sd_journal_add_match(journal: *mut SdJournal, data: *const c_void, size: size_t) -> c_int;
+    fn sd_journal_add_disjunction(journal: *mut SdJournal) -> c_int;
+    fn sd_journal_add_conjunction(journal: *mut SdJournal) -> c_int;
+    fn sd_journal_flush_matches(journal: *mut SdJournal);
+    fn sd_journal_seek_head(journal: *mut SdJournal) -> c_int;
+    fn sd_journal_seek_tail(journal: *mut SdJournal) -> c_int;
+    fn sd_journal_seek_realtime_usec(journal: *mut SdJournal, usec: u64) -> c_int;
+    fn sd_journal_seek_cursor(journal: *mut SdJournal, cursor: *const c_char) -> c_int;
+    fn sd_journal_test_cursor(journal: *mut SdJournal, cursor: *const c_char) -> c_int;
+    fn sd_journal_next(journal: *mut SdJournal) -> c_int;
+    fn sd_journal_previous(journal: *mut SdJournal) -> c_int;
+    fn sd_journal_get_cursor(journal: *mut SdJournal, cursor: *mut *mut c_char) -> c_int;
+    fn sd_journal_get_realtime_usec(journal: *mut SdJournal, usec: *mut u64) -> c_int;
+    fn sd_journal_get_monotonic_usec(
+        journal: *mut SdJournal,
+        usec: *mut u64,
+        boot_id: *mut [u8; 16],
+    ) -> c_int;
+    fn sd_journal_wait(journal: *mut SdJournal, timeout_usec: u64) -> c_int;
+    fn sd_journal_restart_data(journal: *mut SdJournal);
+    fn sd_journal_enumerate_data(
+        journal: *mut SdJournal,
+        data: *mut *const c_void,
+        length: *mut size_t,
+    ) -> c_int;
+    fn sd_journal_query_unique(journal: *mut SdJournal, field: *const c_char) -> c_int;
+    fn sd_journal_enumerate_unique(
+        journal: *mut SdJournal,
+        data: *mut *const c_void,
+        length: *mut size_t,
+    ) -> c_int;
+}
+
+/// Turns the negative errno returned by the `sd_journal` functions into an error message.
+fn check(result: c_int, action: &str) -> Result<c_int, String> {
+    if result < 0 {
+        Err(format!(
+            "Unable to {}: {}",
+            action,
+            io::Error::from_raw_os_error(-result)
+        ))
+    } else {
+        Ok(result)
+    }
+}
+
+/// A single entry of the journal.
+#[derive(Clone, Debug, Default)]
+pub struct Entry {
+    /// Identifies the entry, so that reading can resume from it.
+    pub cursor: String,
+    /// When the entry was written, in microseconds since the epoch.
+    pub realtime: u64,
+    /// When the entry was written, in microseconds since its boot.
+    pub monotonic: u64,
+    /// Every field of the entry, in the order they are stored. Binary values are converted
+    /// lossily to text.
+    pub fields: Vec<(String, String)>,
+    /// The value of every field as it is stored, in the same order as `fields`, so that binary
+    /// values can be exported without losing data.
+    pub raw: Vec<Vec<u8>>,
+}
+
+impl Entry {
+    /// Returns the value of a field of the entry.
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn message(&self) -> &str {
+        self.field("MESSAGE").unwrap_or("")
+    }
+
+    /// The run of the unit the entry belongs to, whether it was logged by the unit's processes
+    /// or by the manager about the unit.
+    pub fn invocation_id(&self) -> Option<&str> {
+        self.field("_SYSTEMD_INVOCATION_ID")
+            .or_else(|| self.field("INVOCATION_ID"))
+            .or_else(|| self.field("USER_INVOCATION_ID"))
+    }
+
+    /// The syslog priority of the entry, from 0 (emerg) to 7 (debug).
+    pub fn priority(&self) -> Option<u8> {
+        self.field("PRIORITY").and_then(|x| x.parse().ok())
+    }
+
+    /// Formats the entry like the default output of `journalctl`, such as
+    /// `Oct 18 12:00:00 host sshd[123]: Accepted publickey`, after the given timestamp.
+    fn line(&self, timestamp: &str) -> String {
+        let identifier = self
+            .field("SYSLOG_IDENTIFIER")
+            .or_else(|| self.field("_COMM"))
+            .unwrap_or("unknown");
+        let pid = self.field("_PID").or_else(|| self.field("SYSLOG_PID"));
+        format!(
+            "{} {} {}{}: {}",
+            timestamp,
+            self.field("_HOSTNAME").unwrap_or("localhost"),
+            identifier,
+            pid.map(|x| format!("[{}]", x)).unwrap_or_default(),
+            self.message()
+        )
+    }
+
+    /// Writes the entry as a JSON object, like `journalctl -o json`. Fields which occur more
+    /// than once are written as an array of their values.
+    fn json(&self) -> String {
+        let mut fields: Vec<(&str, Vec<&str>)> = Vec::new();
+        for (field, value) in &self.fields {
+            match fields.iter_mut().find(|(name, _)| name == field) {
+                Some((_, values)) => values.push(value),
+                None => fields.push((field, vec![value])),
+            }
+        }
+        let realtime = self.realtime.to_string();
+        let monotonic = self.monotonic.to_string();
+        let mut members = vec![
+            format!("\"__CURSOR\":{}", json_string(&self.cursor)),
+            format!("\"__REALTIME_TIMESTAMP\":{}", json_string(&realtime)),
+            format!("\"__MONOTONIC_TIMESTAMP\":{}", json_string(&monotonic)),
+        ];
+        for (field, values) in fields {
+            let value = match values.as_slice() {
+                [value] => json_string(value),
+                values => {
+                    let values: Vec<String> = values.iter().map(|x| json_string(x)).collect();
+                    format!("[{}]", values.join(","))
+                }
+            };
+            members.push(format!("{}:{}", json_string(field), value));
+        }
+        format!("{{{}}}", members.join(","))
+    }
+
+    /// Writes the entry in the journal export format, like `journalctl -o export`. Values which
+    /// aren't valid UTF-8, or have line breaks or other control characters, are written as they
+    /// are stored in the binary form, prefixed with their length.
+    fn export(&self, output: &mut Vec<u8>) {
+        output.extend_from_slice(format!("__CURSOR={}\n", self.cursor).as_bytes());
+        output.extend_from_slice(format!("__REALTIME_TIMESTAMP={}\n", self.realtime).as_bytes());
+        output.extend_from_slice(format!("__MONOTONIC_TIMESTAMP={}\n", self.monotonic).as_bytes());
+        for ((field, _), value) in self.fields.iter().zip(&self.raw) {
+            output.extend_from_slice(field.as_bytes());
+            let printable = str::from_utf8(value).is_ok_and(|x| !x.chars().any(char::is_control));
+            if printable {
+                output.push(b'=');
+            } else {
+                output.push(b'\n');
+                output.extend_from_slice(&(value.len() as u64).to_le_bytes());
+            }
+            output.extend_from_slice(value);
+            output.push(b'\n');
+        }
+        output.push(b'\n');
+    }
+}
+
+/// The formats the journal can be exported in, like the output modes of `journalctl -o`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Short,
+    ShortIso,
+    Json,
+    Export,
+}
+
+impl OutputFormat {
+    pub const ALL: [OutputFormat; 4] = [
+        OutputFormat::Short,
+        OutputFormat::ShortIso,
+        OutputFormat::Json,
+        OutputFormat::Export,
+    ];
+
+    /// The name of the format, as given to `journalctl -o`.
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputFormat::Short => "short",
+            OutputFormat::ShortIso => "short-iso",
+            OutputFormat::Json => "json",
+            OutputFormat::Export => "export",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            OutputFormat::Short => "Plain text",
+            OutputFormat::ShortIso => "Plain text with ISO 8601 timestamps",
+            OutputFormat::Json => "JSON lines",
+            OutputFormat::Export => "Journal export format",
+        }
+    }
+
+    /// The extension of the files written in the format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Short | OutputFormat::ShortIso => "log",
+            OutputFormat::Json => "json",
+            OutputFormat::Export => "export",
+        }
+    }
+
+    /// Writes the entries in the format, one after the other.
+    pub fn write(self, entries: &[Entry]) -> Vec<u8> {
+        let mut output = Vec::new();
+        for entry in entries {
+            match self {
+                OutputFormat::Short => {
+                    output.extend_from_slice(
+                        entry
+                            .line(&format_short_timestamp(entry.realtime))
+                            .as_bytes(),
+                    );
+                    output.push(b'\n');
+                }
+                OutputFormat::ShortIso => {
+                    output.extend_from_slice(
+                        entry.line(&format_iso_timestamp(entry.realtime)).as_bytes(),
+                    );
+                    output.push(b'\n');
+                }
+                OutputFormat::Json => {
+                    output.extend_from_slice(entry.json().as_bytes());
+                    output.push(b'\n');
+                }
+                OutputFormat::Export => entry.export(&mut output),
+            }
+        }
+        output
+    }
+}
+
+/// Formats a timestamp in microseconds since the epoch the way `journalctl` does, such as
+/// `Oct 18 12:00:00`, in the local time zone.
+pub fn format_short_timestamp(usec: u64) -> String {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let time = (usec / 1_000_000) as libc::time_t;
+    let mut tm: libc::tm = unsafe { ::std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        return String::from("n/a");
+    }
+    format!(
+        "{} {:02} {:02}:{:02}:{:02}",
+        MONTHS[tm.tm_mon as usize % 12],
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
+
+/// Formats a timestamp in microseconds since the epoch the way `journalctl -o short-iso` does,
+/// such as `2024-10-18T12:00:00+02:00`, in the local time zone.
+pub fn format_iso_timestamp(usec: u64) -> String {
+    let time = (usec / 1_000_000) as libc::time_t;
+    let mut tm: libc::tm = unsafe { ::std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        return String::from("n/a");
+    }
+    let offset = tm.tm_gmtoff / 60;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec,
+        if offset < 0 { '-' } else { '+' },
+        offset.abs() / 60,
+        offset.abs() % 60
+    )
+}
+
+/// Narrows down the entries read by a `Pager`, like the options of `journalctl`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Filter {
+    /// Only entries at least as important as this priority, like `journalctl -p`.
+    pub priority: Option<u8>,
+    /// Only entries written within this time range, in microseconds since the epoch, like
+    /// `journalctl --since` and `--until`.
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    /// The boot to read the entries of, written without dashes, or `None` for the current boot.
+    pub boot: Option<String>,
+    /// Only entries with these field values, like `journalctl FIELD=value`. Values of the same
+    /// field are alternatives.
+    pub matches: Vec<(String, String)>,
+    /// Only the entries of a single run of the unit, like `journalctl _SYSTEMD_INVOCATION_ID=`.
+    pub invocation: Option<String>,
+}
+
+impl Filter {
+    fn contains(&self, entry: &Entry) -> bool {
+        self.since.is_none_or(|since| entry.realtime >= since)
+            && self.until.is_none_or(|until| entry.realtime <= until)
+    }
+}
+
+/// Parses a time the way `journalctl --since` does: a timestamp such as `2024-01-31 12:00`,
+/// `@1706702400`, `now`, `today`, `yesterday`, `tomorrow`, or a time span relative to now such as
+/// `-1h`, `+30min` or `2 days ago`. Times are in microseconds since the epoch.
+pub fn parse_time(input: &str, now: u64) -> Result<u64, String> {
+    let input = input.trim();
+    let midnight = |after: u64| {
+        calendar::parse("daily")
+            .ok()
+            .and_then(|x| x.next_elapse(after))
+            .ok_or_else(|| format!("Invalid time: {:?}", input))
+    };
+    const DAY: u64 = 86_400_000_000;
+    match input {
+        "now" => return Ok(now),
+        "today" => return midnight(now.saturating_sub(DAY)),
+        "yesterday" => return midnight(now.saturating_sub(2 * DAY)),
+        "tomorrow" => return midnight(now),
+        _ => (),
+    }
+    if let Some(span) = input.strip_prefix('-') {
+        return Ok(now.saturating_sub(timespan::parse(span)?));
+    }
+    if let Some(span) = input.strip_prefix('+') {
+        return Ok(now.saturating_add(timespan::parse(span)?));
+    }
+    if let Some(span) = input.strip_suffix(" ago") {
+        return Ok(now.saturating_sub(timespan::parse(span)?));
+    }
+    calendar::parse(input)?
+        .timestamp()
+        .ok_or_else(|| format!("Not a single point in time: {:?}", input))
+}
+
+/// A boot recorded in the journal, like a row of `journalctl --list-boots`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Boot {
+    /// The boot ID, written without dashes.
+    pub id: String,
+    /// When the first and last entries of the boot were written.
+    pub first: u64,
+    pub last: u64,
+}
+
+/// Lists the boots recorded in the journal, from the most recent.
+pub fn list_boots(user: bool) -> Result<Vec<Boot>, String> {
+    let mut journal = Journal::open(user)?;
+    let mut boots = Vec::new();
+    for id in journal.unique_values("_BOOT_ID")? {
+        journal.flush_matches();
+        journal.add_match("_BOOT_ID", &id)?;
+        let first = journal.next_entries(None, 1)?;
+        let last = journal.previous_entries(None, 1)?;
+        if let (Some(first), Some(last)) = (first.first(), last.first()) {
+            boots.push(Boot {
+                id,
+                first: first.realtime,
+                last: last.realtime,
+            });
+        }
+    }
+    boots.sort_by_key(|x| Reverse(x.first));
+    Ok(boots)
+}
+
+/// The journal files of the local machine, read through `sd-journal`.
+pub struct Journal {
+    raw: *mut SdJournal,
+}
+
+impl Journal {
+    /// Opens the journal of the system, or of the current user when managing user units.
+    pub fn open(user: bool) -> Result<Journal, String> {
+        let flags = if user {
+            SD_JOURNAL_CURRENT_USER
+        } else {
+            SD_JOURNAL_SYSTEM
+        };
+        let mut raw = ptr::null_mut();
+        check(
+            unsafe { sd_journal_open(&mut raw, SD_JOURNAL_LOCAL_ONLY | flags) },
+            "open the journal",
+        )?;
+        Ok(Journal { raw })
+    }
+
+    /// Only reads the entries where the field has the given value. Matches of different fields
+    /// must all be satisfied, while matches of the same field are alternatives.
+    pub fn add_match(&mut self, field: &str, value: &str) -> Result<(), String> {
+        let data = format!("{}={}", field, value);
+        check(
+            unsafe { sd_journal_add_match(self.raw, data.as_ptr() as *const c_void, data.len()) },
+            "filter the journal",
+        )
+        .map(|_| ())
+    }
+
+    /// Starts a new group of matches, so that entries satisfying either group are read.
+    pub fn add_disjunction(&mut self) -> Result<(), String> {
+        check(
+            unsafe { sd_journal_add_disjunction(self.raw) },
+            "filter the journal",
+        )
+        .map(|_| ())
+    }
+
+    /// Requires the entries to satisfy both the matches added so far and those added next.
+    pub fn add_conjunction(&mut self) -> Result<(), String> {
+        check(
+            unsafe { sd_journal_add_conjunction(self.raw) },
+            "filter the journal",
+        )
+        .map(|_| ())
+    }
+
+    /// Only reads the entries about the given unit, like `journalctl -u` or
+    /// `journalctl --user -u`, including the messages of the manager about it.
+    pub fn match_unit(&mut self, unit: &str, user: bool) -> Result<(), String> {
+        if user {
+            let uid = unsafe { libc::getuid() }.to_string();
+            self.add_match("_SYSTEMD_USER_UNIT", unit)?;
+            self.add_match("_UID", &uid)?;
+            self.add_disjunction()?;
+            self.add_match("USER_UNIT", unit)?;
+            self.add_match("_UID", &uid)?;
+            self.add_disjunction()?;
+            self.add_match("MESSAGE_ID", COREDUMP_MESSAGE_ID)?;
+            self.add_match("COREDUMP_USER_UNIT", unit)?;
+            self.add_match("_UID", &uid)?;
+            self.add_disjunction()?;
+            self.add_match("OBJECT_SYSTEMD_USER_UNIT", unit)?;
+            self.add_match("_UID", &uid)?;
+        } else {
+            self.add_match("_SYSTEMD_UNIT", unit)?;
+            self.add_disjunction()?;
+            self.add_match("MESSAGE_ID", COREDUMP_MESSAGE_ID)?;
+            self.add_match("COREDUMP_UNIT", unit)?;
+            self.add_match("_UID", "0")?;
+            self.add_disjunction()?;
+            self.add_match("UNIT", unit)?;
+            self.add_match("_PID", "1")?;
+            self.add_disjunction()?;
+            self.add_match("OBJECT_SYSTEMD_UNIT", unit)?;
+            self.add_match("_UID", "0")?;
+        }
+        self.add_conjunction()
+    }
+
+    /// Only reads the entries of the given boot, written without dashes.
+    pub fn match_boot(&mut self, boot_id: &str) -> Result<(), String> {
+        self.add_match("_BOOT_ID", boot_id)?;
+        self.add_conjunction()
+    }
+
+    /// Only reads the entries of the given run of a unit, written by its processes or by the
+    /// manager about it.
+    pub fn match_invocation(&mut self, invocation: &str) -> Result<(), String> {
+        self.add_match("_SYSTEMD_INVOCATION_ID", invocation)?;
+        self.add_disjunction()?;
+        self.add_match("INVOCATION_ID", invocation)?;
+        self.add_disjunction()?;
+        self.add_match("USER_INVOCATION_ID", invocation)?;
+        self.add_conjunction()
+    }
+
+    /// Only reads the entries at least as important as the given priority.
+    pub fn match_priority(&mut self, priority: u8) -> Result<(), String> {
+        for level in 0..=priority.min(7) {
+            self.add_match("PRIORITY", &level.to_string())?;
+        }
+        self.add_conjunction()
+    }
+
+    /// Removes every match, so that every entry is read again.
+    pub fn flush_matches(&mut self) {
+        unsafe { sd_journal_flush_matches(self.raw) };
+    }
+
+    /// Lists the distinct values of a field across the journal, ignoring the matches.
+    pub fn unique_values(&mut self, field: &str) -> Result<Vec<String>, String> {
+        let name = CString::new(field).map_err(|_| format!("Invalid field: {:?}", field))?;
+        check(
+            unsafe { sd_journal_query_unique(self.raw, name.as_ptr()) },
+            "query the journal",
+        )?;
+        let mut values = Vec::new();
+        loop {
+            let mut data: *const c_void = ptr::null();
+            let mut length: size_t = 0;
+            let result = unsafe { sd_journal_enumerate_unique(self.raw, &mut data, &mut length) };
+            if check(result, "query the journal")? == 0 {
+                break;
+            }
+            let data = unsafe { ::std::slice::from_raw_parts(data as *const u8, length) };
+            let data = String::from_utf8_lossy(data);
+            if let Some(value) = data.strip_prefix(field).and_then(|x| x.strip_prefix('=')) {
+                values.push(value.to_owned());
+            }
+        }
+        Ok(values)
+    }
+
+    /// Reads up to `count` entries written at or before the given time, from the newest to the
+    /// oldest.
+    pub fn entries_before(&mut self, usec: u64, count: usize) -> Result<Vec<Entry>, String> {
+        check(
+            unsafe { sd_journal_seek_realtime_usec(self.raw, usec.saturating_add(1)) },
+            "seek the journal",
+        )?;
+        self.read(count, false)
+    }
+
+    /// Reads up to `count` entries older than the one at the cursor, or the most recent entries
+    /// without a cursor. The entries are returned from the newest to the oldest.
+    pub fn previous_entries(
+        &mut self,
+        cursor: Option<&str>,
+        count: usize,
+    ) -> Result<Vec<Entry>, String> {
+        match cursor {
+            Some(cursor) => self.seek_past(cursor, false)?,
+            None => {
+                check(
+                    unsafe { sd_journal_seek_tail(self.raw) },
+                    "seek the journal",
+                )?;
+            }
+        }
+        self.read(count, false)
+    }
+
+    /// Reads up to `count` entries newer than the one at the cursor, or the oldest entries
+    /// without a cursor. The entries are returned from the oldest to the newest.
+    pub fn next_entries(
+        &mut self,
+        cursor: Option<&str>,
+        count: usize,
+    ) -> Result<Vec<Entry>, String> {
+        match cursor {
+            Some(cursor) => self.seek_past(cursor, true)?,
+            None => {
+                check(
+                    unsafe { sd_journal_seek_head(self.raw) },
+                    "seek the journal",
+                )?;
+            }
+        }
+        self.read(count, true)
+    }
+
+    /// Takes in the entries written and the journal files rotated since the journal was opened
+    /// or last refreshed, without waiting for any.
+    pub fn refresh(&mut self) -> Result<(), String> {
+        check(unsafe { sd_journal_wait(self.raw, 0) }, "watch the journal").map(|_| ())
+    }
+
+    /// Moves to the next or previous entry, returning whether there was one.
+    fn step(&mut self, forward: bool) -> Result<bool, String> {
+        let result = unsafe {
+            if forward {
+                sd_journal_next(self.raw)
+            } else {
+                sd_journal_previous(self.raw)
+            }
+        };
+        check(result, "read the journal").map(|moved| moved > 0)
+    }
+
+    /// Seeks to the entry at the cursor, so that moving in the given direction reads the
+    /// entries past it.
+    fn seek_past(&mut self, cursor: &str, forward: bool) -> Result<(), String> {
+        let cursor = CString::new(cursor).map_err(|_| String::from("Invalid journal cursor"))?;
+        check(
+            unsafe { sd_journal_seek_cursor(self.raw, cursor.as_ptr()) },
+            "seek the journal",
+        )?;
+        // Seeking only takes effect once moving, which lands on the entry itself.
+        if self.step(forward)? && unsafe { sd_journal_test_cursor(self.raw, cursor.as_ptr()) } <= 0
+        {
+            // The entry was rotated away, so the journal already moved past it.
+            self.step(!forward)?;
+        }
+        Ok(())
+    }
+
+    /// Moves through the journal in the given direction, reading up to `count` entries.
+    fn read(&mut self, count: usize, forward: bool) -> Result<Vec<Entry>, String> {
+        let mut entries = Vec::new();
+        while entries.len() < count && self.step(forward)? {
+            entries.push(self.current_entry()?);
+        }
+        Ok(entries)
+    }
+
+    /// Reads every field of the entry the journal is positioned at.
+    fn current_entry(&mut self) -> Result<Entry, String> {
+        let mut entry = Entry::default();
+
+        let mut cursor: *mut c_char = ptr::null_mut();
+        check(
+            unsafe { sd_journal_get_cursor(self.raw, &mut cursor) },
+            "read the journal cursor",
+        )?;
+        entry.cursor = unsafe { CStr::from_ptr(cursor) }
+            .to_string_lossy()
+            .into_owned();
+        unsafe { libc::free(cursor as *mut c_void) };
+
+        check(
+            unsafe { sd_journal_get_realtime_usec(self.raw, &mut entry.realtime) },
+            "read the journal timestamp",
+        )?;
+        // Without a boot ID to fill in, entries of other boots are refused.
+        let mut boot_id = [0; 16];
+        check(
+            unsafe { sd_journal_get_monotonic_usec(self.raw, &mut entry.monotonic, &mut boot_id) },
+            "read the journal timestamp",
+        )?;
+
+        unsafe { sd_journal_restart_data(self.raw) };
+        loop {
+            let mut data: *const c_void = ptr::null();
+            let mut length: size_t = 0;
+            let result = unsafe { sd_journal_enumerate_data(self.raw, &mut data, &mut length) };
+            if check(result, "read the journal entry")? == 0 {
+                break;
+            }
+            let data = unsafe { ::std::slice::from_raw_parts(data as *const u8, length) };
+            if let Some(equals) = data.iter().position(|&x| x == b'=') {
+                entry.fields.push((
+                    String::from_utf8_lossy(&data[..equals]).into_owned(),
+                    String::from_utf8_lossy(&data[equals + 1..]).into_owned(),
+                ));
+                entry.raw.push(data[equals + 1..].to_vec());
+            }
+        }
+        Ok(entry)
+    }
+}
+
+impl Drop for Journal {
+    fn drop(&mut self) {
+        unsafe { sd_journal_close(self.raw) };
+    }
+}
+
+/// Reads the journal of a unit during a boot a page at a time, starting from the most recent
+/// entries, like `journalctl -b -u` does.
+pub struct Pager {
+    journal: Journal,
+    filter: Filter,
+    /// The cursor of the oldest entry read so far, from which older pages are read.
+    oldest: Option<String>,
+    /// The cursor of the newest entry read so far, from which newer entries are read.
+    newest: Option<String>,
+    complete: bool,
+}
+
+impl Pager {
+    pub fn open(unit: &str, user: bool, filter: &Filter) -> Result<Pager, String> {
+        let mut journal = Journal::open(user)?;
+        journal.match_unit(unit, user)?;
+        match filter.boot {
+            Some(ref boot_id) => journal.match_boot(boot_id)?,
+            None => {
+                if let Ok(boot_id) = history::current_boot_id() {
+                    journal.match_boot(&boot_id)?;
+                }
+            }
+        }
+        if let Some(priority) = filter.priority {
+            journal.match_priority(priority)?;
+        }
+        if !filter.matches.is_empty() {
+            for (field, value) in &filter.matches {
+                journal.add_match(field, value)?;
+            }
+            journal.add_conjunction()?;
+        }
+        if let Some(ref invocation) = filter.invocation {
+            journal.match_invocation(invocation)?;
+        }
+        Ok(Pager {
+            journal,
+            filter: filter.clone(),
+            oldest: None,
+            newest: None,
+            complete: false,
+        })
+    }
+
+    /// Reads the next page of older entries, from the newest to the oldest. The first page holds
+    /// the most recent entries.
+    pub fn older(&mut self, count: usize) -> Result<Vec<Entry>, String> {
+        if self.complete {
+            return Ok(Vec::new());
+        }
+        let mut entries = match (self.oldest.as_deref(), self.filter.until) {
+            (None, Some(until)) => self.journal.entries_before(until, count)?,
+            (oldest, _) => self.journal.previous_entries(oldest, count)?,
+        };
+        if entries.len() < count {
+            self.complete = true;
+        }
+        if let Some(entry) = entries.last() {
+            self.oldest = Some(entry.cursor.clone());
+        }
+        if self.newest.is_none() {
+            self.newest = entries.first().map(|x| x.cursor.clone());
+        }
+        // Entries are only roughly ordered by time, so the reading stops at the first one which
+        // is too old.
+        if let Some(since) = self.filter.since {
+            if let Some(index) = entries.iter().position(|x| x.realtime < since) {
+                entries.truncate(index);
+                self.complete = true;
+            }
+        }
+        entries.retain(|x| self.filter.contains(x));
+        Ok(entries)
+    }
+
+    /// Reads the entries written since the newest entry read so far, from the oldest to the
+    /// newest, like `journalctl -f` does. At most `count` entries are read at a time.
+    pub fn newer(&mut self, count: usize) -> Result<Vec<Entry>, String> {
+        self.journal.refresh()?;
+        let mut entries = self.journal.next_entries(self.newest.as_deref(), count)?;
+        if let Some(entry) = entries.last() {
+            self.newest = Some(entry.cursor.clone());
+        }
+        entries.retain(|x| self.filter.contains(x));
+        Ok(entries)
+    }
+}
+
+/// Reads every entry of the unit which passes the filter, from the oldest to the newest.
+pub fn read_entries(unit: &str, user: bool, filter: &Filter) -> Result<Vec<Entry>, String> {
+    let mut pager = Pager::open(unit, user, filter)?;
+    let mut entries = Vec::new();
+    while !pager.complete {
+        entries.extend(pager.older(1000)?);
+    }
+    entries.reverse();
+    Ok(entries)
+}
+
+/// A single run of a unit, from when it was started until it stopped, as told by its entries.
+#[derive(Clone, Debug)]
+pub struct Invocation {
+    pub id: String,
+    /// How the run ended, such as `success` or `exit-code`, or `None` if the journal doesn't
+    /// tell, such as while the unit is still running.
+    pub result: Option<String>,
+    /// How the main process exited, such as `exited, status=1`.
+    pub exit: Option<String>,
+    /// When the first entry of the run was written.
+    pub started: u64,
+    /// When the last entry of the run was written.
+    pub last: u64,
+    /// How many entries were written about the run.
+    pub entries: usize,
+}
+
+impl Invocation {
+    /// Formats the entries of the run like `journalctl`, but with the time passed since the run
+    /// started in place of the timestamp, so that runs can be compared line by line.
+    pub fn transcript(&self, unit: &str, user: bool, boot: Option<&str>) -> Result<String, String> {
+        let filter = Filter {
+            boot: boot.map(str::to_owned),
+            invocation: Some(self.id.clone()),
+            ..Filter::default()
+        };
+        let mut output = String::new();
+        for entry in read_entries(unit, user, &filter)? {
+            let offset = entry.realtime.saturating_sub(self.started);
+            let offset = format!("+{}.{:06}s", offset / 1_000_000, offset % 1_000_000);
+            output.push_str(&entry.line(&offset));
+            output.push('\n');
+        }
+        Ok(output)
+    }
+}
+
+/// Groups the entries of the unit during the filter's boot by the run of the unit they belong
+/// to, from the most recent run. The other parts of the filter are ignored, as the messages of
+/// the manager about each run are needed to tell how it ended. The entries are only summarized
+/// as they are read, a page at a time, so that long journals aren't held in memory.
+pub fn list_invocations(
+    unit: &str,
+    user: bool,
+    filter: &Filter,
+) -> Result<Vec<Invocation>, String> {
+    let boot = Filter {
+        boot: filter.boot.clone(),
+        ..Filter::default()
+    };
+    let mut pager = Pager::open(unit, user, &boot)?;
+    let mut invocations: Vec<Invocation> = Vec::new();
+    while !pager.complete {
+        // Pages are read from the newest entry, so the first messages read about how a run
+        // ended are the latest ones.
+        for entry in pager.older(1000)? {
+            let id = match entry.invocation_id() {
+                Some(id) => id,
+                None => continue,
+            };
+            let index = match invocations.iter().position(|x| x.id == id) {
+                Some(index) => index,
+                None => {
+                    invocations.push(Invocation {
+                        id: id.to_owned(),
+                        result: None,
+                        exit: None,
+                        started: entry.realtime,
+                        last: entry.realtime,
+                        entries: 0,
+                    });
+                    invocations.len() - 1
+                }
+            };
+            let invocation = &mut invocations[index];
+            invocation.started = invocation.started.min(entry.realtime);
+            invocation.last = invocation.last.max(entry.realtime);
+            invocation.entries += 1;
+            match entry.field("MESSAGE_ID") {
+                // Failures take precedence over the messages telling the unit stopped.
+                Some(UNIT_RESULT_MESSAGE_ID) | Some(UNIT_FAILED_MESSAGE_ID)
+                    if invocation.result.as_deref().is_none_or(|x| x == "success") =>
+                {
+                    let result = entry.field("UNIT_RESULT").unwrap_or("failed");
+                    invocation.result = Some(result.to_owned());
+                }
+                Some(UNIT_SUCCESS_MESSAGE_ID) | Some(UNIT_STOPPED_MESSAGE_ID)
+                    if invocation.result.is_none() =>
+                {
+                    invocation.result = Some(String::from("success"));
+                }
+                Some(UNIT_PROCESS_EXIT_MESSAGE_ID) if invocation.exit.is_none() => {
+                    let code = entry.field("EXIT_CODE").unwrap_or("exited");
+                    if let Some(status) = entry.field("EXIT_STATUS") {
+                        invocation.exit = Some(format!("{}, status={}", code, status));
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+    invocations.sort_by_key(|x| Reverse(x.started));
+    Ok(invocations)
+}
+
+/// The signals which make a process dump core, by number, for the entries which predate
+/// `COREDUMP_SIGNAL_NAME`.
+const CORE_SIGNALS: [(&str, &str); 10] = [
+    ("3", "SIGQUIT"),
+    ("4", "SIGILL"),
+    ("5", "SIGTRAP"),
+    ("6", "SIGABRT"),
+    ("7", "SIGBUS"),
+    ("8", "SIGFPE"),
+    ("11", "SIGSEGV"),
+    ("24", "SIGXCPU"),
+    ("25", "SIGXFSZ"),
+    ("31", "SIGSYS"),
+];
+
+/// A process which crashed and had its core dumped by `systemd-coredump`, like a line of
+/// `coredumpctl list`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Crash {
+    /// Identifies the journal entry of the crash.
+    pub cursor: String,
+    /// When the crash was logged, in microseconds since the epoch.
+    pub time: u64,
+    pub executable: String,
+    pub pid: String,
+    pub signal: String,
+    /// The unit the process belonged to, if any.
+    pub unit: Option<String>,
+    /// The backtrace of the threads, when `systemd-coredump` could produce one.
+    pub stack_trace: Option<String>,
+}
+
+impl Crash {
+    fn new(entry: &Entry, user: bool) -> Crash {
+        let signal = match entry.field("COREDUMP_SIGNAL_NAME") {
+            Some(name) => name.to_owned(),
+            None => {
+                let number = entry.field("COREDUMP_SIGNAL").unwrap_or("?");
+                CORE_SIGNALS
+                    .iter()
+                    .find(|x| x.0 == number)
+                    .map_or_else(|| format!("signal {}", number), |x| x.1.to_owned())
+            }
+        };
+        let unit = if user {
+            entry.field("COREDUMP_USER_UNIT")
+        } else {
+            entry.field("COREDUMP_UNIT")
+        };
+        // The message starts with a summary line, followed by the modules and threads.
+        let stack_trace = entry
+            .message()
+            .split_once('\n')
+            .map(|x| x.1.trim())
+            .filter(|x| x.contains("Stack trace"));
+        Crash {
+            cursor: entry.cursor.clone(),
+            time: entry.realtime,
+            executable: entry
+                .field("COREDUMP_EXE")
+                .or_else(|| entry.field("COREDUMP_COMM"))
+                .unwrap_or("?")
+                .to_owned(),
+            pid: entry.field("COREDUMP_PID").unwrap_or("?").to_owned(),
+            signal,
+            unit: unit.map(str::to_owned),
+            stack_trace: stack_trace.map(str::to_owned),
+        }
+    }
+}
+
+/// Lists up to `count` of the most recent crashes recorded in the journal, from the newest,
+/// either of every process or only of the processes of a unit.
+pub fn list_crashes(user: bool, unit: Option<&str>, count: usize) -> Result<Vec<Crash>, String> {
+    let mut journal = Journal::open(user)?;
+    journal.add_match("MESSAGE_ID", COREDUMP_MESSAGE_ID)?;
+    if user {
+        let uid = unsafe { libc::getuid() }.to_string();
+        journal.add_match("COREDUMP_UID", &uid)?;
+    }
+    if let Some(unit) = unit {
+        let field = if user {
+            "COREDUMP_USER_UNIT"
+        } else {
+            "COREDUMP_UNIT"
+        };
+        journal.add_match(field, unit)?;
+    }
+    Ok(journal
+        .previous_entries(None, count)?
+        .iter()
+        .map(|x| Crash::new(x, user))
+        .collect())
+}
+
+/// Finds text within the journal, either literally or as a regular expression. Like
+/// `journalctl --grep`, the search ignores case unless the pattern has uppercase letters.
+pub struct Search {
+    regex: Regex,
+}
+
+impl Search {
+    pub fn new(pattern: &str, regex: bool) -> Result<Search, String> {
+        let pattern = if regex {
+            pattern.to_owned()
+        } else {
+            self::regex::escape(pattern)
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!pattern.chars().any(char::is_uppercase))
+            .build()
+            .map(|regex| Search { regex })
+            .map_err(|error| error.to_string())
+    }
+
+    /// Finds every match within the text, as the byte ranges they span. Empty matches are
+    /// skipped.
+    pub fn find_all(&self, text: &str) -> Vec<(usize, usize)> {
+        self.regex
+            .find_iter(text)
+            .filter(|x| x.start() < x.end())
+            .map(|x| (x.start(), x.end()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_and_absolute_times() {
+        let now = 1_700_000_000_000_000;
+        assert_eq!(parse_time("now", now), Ok(now));
+        assert_eq!(parse_time("-1h", now), Ok(now - 3_600_000_000));
+        assert_eq!(parse_time("+5min", now), Ok(now + 300_000_000));
+        assert_eq!(parse_time("2 days ago", now), Ok(now - 172_800_000_000));
+        assert_eq!(
+            parse_time("2014-03-25 03:59:56 UTC", now),
+            Ok(1_395_719_996_000_000)
+        );
+        assert_eq!(parse_time("@1395716396", now), Ok(1_395_716_396_000_000));
+        assert!(parse_time("*-*-* 12:00", now).is_err());
+    }
+
+    #[test]
+    fn json_and_export_formats() {
+        let fields = [
+            ("MESSAGE", &b"a \"quoted\"\nline"[..]),
+            ("TAG", b"x"),
+            ("TAG", b"y"),
+            ("DATA", b"\xff\x00"),
+        ];
+        let entry = Entry {
+            cursor: String::from("s=1"),
+            realtime: 10,
+            monotonic: 5,
+            fields: fields
+                .iter()
+                .map(|(name, value)| {
+                    let value = String::from_utf8_lossy(value).into_owned();
+                    (name.to_string(), value)
+                })
+                .collect(),
+            raw: fields.iter().map(|(_, value)| value.to_vec()).collect(),
+        };
+        assert_eq!(
+            OutputFormat::Json.write(::std::slice::from_ref(&entry)),
+            b"{\"__CURSOR\":\"s=1\",\"__REALTIME_TIMESTAMP\":\"10\",\
+              \"__MONOTONIC_TIMESTAMP\":\"5\",\"MESSAGE\":\"a \\\"quoted\\\"\\nline\",\
+              \"TAG\":[\"x\",\"y\"],\"DATA\":\"\xef\xbf\xbd\\u0000\"}\n"
+                .to_vec()
+        );
+        let mut expected = b"__CURSOR=s=1\n__REALTIME_TIMESTAMP=10\n__MONOTONIC_TIMESTAMP=5\n\
+                             MESSAGE\n"
+            .to_vec();
+        expected.extend_from_slice(&15u64.to_le_bytes());
+        expected.extend_from_slice(b"a \"quoted\"\nline\nTAG=x\nTAG=y\nDATA\n");
+        expected.extend_from_slice(&2u64.to_le_bytes());
+        expected.extend_from_slice(b"\xff\x00\n\n");
+        assert_eq!(OutputFormat::Export.write(&[entry]), expected);
+    }
+
+    #[test]
+    fn crashes_from_coredump_entries() {
+        let field = |name: &str, value: &str| (String::from(name), String::from(value));
+        let mut entry = Entry {
+            cursor: String::from("s=2"),
+            realtime: 20,
+            monotonic: 7,
+            fields: vec![
+                field(
+                    "MESSAGE",
+                    "Process 42 (cat) of user 0 dumped core.\n\n\
+                     Stack trace of thread 42:\n#0  0x00007f main (cat)\n",
+                ),
+                field("COREDUMP_PID", "42"),
+                field("COREDUMP_COMM", "cat"),
+                field("COREDUMP_SIGNAL", "11"),
+                field("COREDUMP_UNIT", "cat.service"),
+            ],
+            ..Entry::default()
+        };
+        assert_eq!(
+            Crash::new(&entry, false),
+            Crash {
+                cursor: String::from("s=2"),
+                time: 20,
+                executable: String::from("cat"),
+                pid: String::from("42"),
+                signal: String::from("SIGSEGV"),
+                unit: Some(String::from("cat.service")),
+                stack_trace: Some(String::from(
+                    "Stack trace of thread 42:\n#0  0x00007f main (cat)"
+                )),
+            }
+        );
+        entry.fields[0] = field("MESSAGE", "Process 42 (cat) of user 0 dumped core.");
+        entry.fields.push(field("COREDUMP_SIGNAL_NAME", "SIGABRT"));
+        let crash = Crash::new(&entry, true);
+        assert_eq!(crash.signal, "SIGABRT");
+        assert_eq!(crash.unit, None);
+        assert_eq!(crash.stack_trace, None);
+    }
+
+    #[test]
+    fn search_is_literal_and_smart_case() {
+        let text = "Started a.b and A.B, then axb";
+        let literal = Search::new("a.b", false).unwrap();
+        assert_eq!(literal.find_all(text), vec![(8, 11), (16, 19)]);
+        let regex = Search::new("a.b", true).unwrap();
+        assert_eq!(regex.find_all(text), vec![(8, 11), (16, 19), (26, 29)]);
+        let cased = Search::new("A.B", false).unwrap();
+        assert_eq!(cased.find_all(text), vec![(16, 19)]);
+        assert!(Search::new("(", true).is_err());
+    }
+}
@@ -0,0 +1,18 @@
+/// Writes a string as a JSON string literal.
+pub fn json_string(value: &str) -> String {
+    let mut output = String::with_capacity(value.len() + 2);
+    output.push('"');
+    for character in value.chars() {
+        match character {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\t' => output.push_str("\\t"),
+            '\r' => output.push_str("\\r"),
+            x if (x as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", x as u32)),
+            x => output.push(x),
+        }
+    }
+    output.push('"');
+    output
+}
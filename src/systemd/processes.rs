@@ -0,0 +1,100 @@
+extern crate libc;
+use std::ffi::CStr;
+use std::fs;
+
+/// A process running within the control group of a unit.
+#[derive(Clone, Debug)]
+pub struct Process {
+    pub pid: u32,
+    pub cgroup: String,
+    pub command: String,
+    pub user: String,
+    /// The CPU time consumed by the process so far, in microseconds.
+    pub cpu_time: u64,
+    /// The resident memory of the process, in bytes.
+    pub memory: u64,
+}
+
+impl Process {
+    /// Completes the information reported by `GetUnitProcesses` with the owner and resource
+    /// usage of the process, as found in `/proc`. Processes which exited in the meantime are
+    /// reported without any usage.
+    pub fn new(cgroup: String, pid: u32, command: String) -> Process {
+        let mut process = Process {
+            pid,
+            cgroup,
+            command,
+            user: String::new(),
+            cpu_time: 0,
+            memory: 0,
+        };
+
+        if let Ok(status) = fs::read_to_string(format!("/proc/{}/status", pid)) {
+            for line in status.lines() {
+                let mut fields = line.split_whitespace();
+                match fields.next() {
+                    Some("Uid:") => {
+                        if let Some(uid) = fields.next().and_then(|x| x.parse().ok()) {
+                            process.user = user_name(uid);
+                        }
+                    }
+                    Some("VmRSS:") => {
+                        let kib = fields.next().and_then(|x| x.parse::<u64>().ok());
+                        process.memory = kib.unwrap_or(0) * 1024;
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        if let Ok(stat) = fs::read_to_string(format!("/proc/{}/stat", pid)) {
+            process.cpu_time = parse_cpu_time(&stat);
+        }
+
+        process
+    }
+}
+
+/// Sums the user and system time found in the contents of `/proc/<pid>/stat`, in
+/// microseconds. The fields are counted from the end of the command name, which may itself
+/// contain spaces and parentheses.
+fn parse_cpu_time(stat: &str) -> u64 {
+    let fields: Vec<&str> = match stat.rfind(')') {
+        Some(end) => stat[end + 1..].split_whitespace().collect(),
+        None => return 0,
+    };
+    // utime and stime are the 14th and 15th fields, the first two being the PID and command.
+    let ticks: u64 = fields
+        .iter()
+        .skip(11)
+        .take(2)
+        .filter_map(|x| x.parse::<u64>().ok())
+        .sum();
+    let ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if ticks_per_second <= 0 {
+        return 0;
+    }
+    ticks * 1_000_000 / ticks_per_second as u64
+}
+
+/// Looks up the name of the user with the given ID, falling back to the ID itself.
+fn user_name(uid: libc::uid_t) -> String {
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buffer = vec![0 as libc::c_char; 4096];
+    let mut result = std::ptr::null_mut();
+    let status = unsafe {
+        libc::getpwuid_r(
+            uid,
+            &mut passwd,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        )
+    };
+    if status != 0 || result.is_null() {
+        return uid.to_string();
+    }
+    unsafe { CStr::from_ptr(passwd.pw_name) }
+        .to_string_lossy()
+        .into_owned()
+}
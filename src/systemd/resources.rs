@@ -0,0 +1,427 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+use systemd::dbus::Properties;
+
+/// The root of the unified (v2) control group hierarchy.
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// The resource usage of a unit at a point in time. Counters which aren't being tracked for
+/// the unit are `None`.
+#[derive(Clone, Debug)]
+pub struct Sample {
+    pub taken: Instant,
+    /// The CPU time consumed so far, in nanoseconds.
+    pub cpu: Option<u64>,
+    pub memory: Option<u64>,
+    pub memory_peak: Option<u64>,
+    pub io_read: Option<u64>,
+    pub io_write: Option<u64>,
+    pub tasks: Option<u64>,
+}
+
+impl Sample {
+    /// Reads the resource accounting properties of a unit.
+    pub fn from_properties(properties: &Properties) -> Sample {
+        Sample {
+            taken: Instant::now(),
+            cpu: properties.counter("CPUUsageNSec"),
+            memory: properties.counter("MemoryCurrent"),
+            memory_peak: properties.counter("MemoryPeak"),
+            io_read: properties.counter("IOReadBytes"),
+            io_write: properties.counter("IOWriteBytes"),
+            tasks: properties.counter("TasksCurrent"),
+        }
+    }
+
+    /// Reads the accounting files of a control group directory in the unified hierarchy.
+    pub fn from_cgroup(path: &Path) -> Sample {
+        let read = |file: &str| fs::read_to_string(path.join(file)).ok();
+        let number = |file: &str| read(file).and_then(|x| x.trim().parse::<u64>().ok());
+
+        let cpu = read("cpu.stat").and_then(|stat| {
+            stat.lines()
+                .find(|line| line.starts_with("usage_usec "))
+                .and_then(|line| line["usage_usec ".len()..].trim().parse::<u64>().ok())
+                .map(|usec| usec * 1000)
+        });
+
+        let (io_read, io_write) = match read("io.stat") {
+            Some(stat) => {
+                let (read, write) = parse_io_stat(&stat);
+                (Some(read), Some(write))
+            }
+            None => (None, None),
+        };
+
+        Sample {
+            taken: Instant::now(),
+            cpu,
+            memory: number("memory.current"),
+            memory_peak: number("memory.peak"),
+            io_read,
+            io_write,
+            tasks: number("pids.current"),
+        }
+    }
+
+    /// The rate at which a counter grew per second since an earlier sample.
+    pub fn rate<F: Fn(&Sample) -> Option<u64>>(&self, earlier: &Sample, counter: F) -> Option<f64> {
+        let seconds = self.taken.duration_since(earlier.taken).as_secs_f64();
+        match (counter(earlier), counter(self)) {
+            (Some(before), Some(after)) if seconds > 0.0 => {
+                Some(after.saturating_sub(before) as f64 / seconds)
+            }
+            _ => None,
+        }
+    }
+
+    /// The share of a single CPU used since an earlier sample, in percent.
+    pub fn cpu_percent(&self, earlier: &Sample) -> Option<f64> {
+        self.rate(earlier, |x| x.cpu)
+            .map(|nsec| nsec / 10_000_000.0)
+    }
+}
+
+/// Sums the bytes read and written across every device listed in an `io.stat` file.
+fn parse_io_stat(stat: &str) -> (u64, u64) {
+    let (mut read, mut write) = (0, 0);
+    for field in stat.split_whitespace() {
+        if let Some(bytes) = field.strip_prefix("rbytes=") {
+            read += bytes.parse::<u64>().unwrap_or(0);
+        } else if let Some(bytes) = field.strip_prefix("wbytes=") {
+            write += bytes.parse::<u64>().unwrap_or(0);
+        }
+    }
+    (read, write)
+}
+
+/// A rolling window of the most recent samples taken for a unit.
+#[derive(Clone, Debug)]
+pub struct History {
+    samples: VecDeque<Sample>,
+    capacity: usize,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> History {
+        History {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Appends a sample, discarding the oldest one if the window is full.
+    pub fn push(&mut self, sample: Sample) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn latest(&self) -> Option<&Sample> {
+        self.samples.back()
+    }
+
+    /// The values of a counter in each sample, with untracked values counted as zero.
+    pub fn values<F: Fn(&Sample) -> Option<u64>>(&self, counter: F) -> Vec<f64> {
+        self.samples
+            .iter()
+            .map(|x| counter(x).unwrap_or(0) as f64)
+            .collect()
+    }
+
+    /// The per second growth of a counter between each pair of consecutive samples.
+    pub fn rates<F: Fn(&Sample) -> Option<u64>>(&self, counter: F) -> Vec<f64> {
+        self.samples
+            .iter()
+            .zip(self.samples.iter().skip(1))
+            .map(|(earlier, later)| later.rate(earlier, &counter).unwrap_or(0.0))
+            .collect()
+    }
+
+    /// The CPU usage in percent between each pair of consecutive samples.
+    pub fn cpu_percent(&self) -> Vec<f64> {
+        self.rates(|x| x.cpu)
+            .into_iter()
+            .map(|nsec| nsec / 10_000_000.0)
+            .collect()
+    }
+}
+
+/// Collects the usage of every service, scope and slice in the unified control group
+/// hierarchy, like `systemd-cgtop` does. Each unit is named after its control group directory.
+pub fn read_cgroups() -> Vec<(String, Sample)> {
+    let mut units = Vec::new();
+    let mut pending = vec![Path::new(CGROUP_ROOT).to_path_buf()];
+    while let Some(directory) = pending.pop() {
+        let entries = match fs::read_dir(&directory) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            if !path.is_dir() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.ends_with(".service") || name.ends_with(".scope") || name.ends_with(".slice") {
+                units.push((name, Sample::from_cgroup(&path)));
+            }
+            pending.push(path);
+        }
+    }
+    units
+}
+
+/// How the value of a resource control setting is written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ControlKind {
+    /// A share of a CPU in percent, stored as microseconds of CPU time per second.
+    Quota,
+    /// A byte count, which may use the K, M, G and T suffixes.
+    Bytes,
+    /// A plain number, such as a task count.
+    Count,
+    /// A relative weight between 1 and 10000.
+    Weight,
+}
+
+/// A resource control setting which can be changed on a running unit with
+/// `SetUnitProperties`, such as `MemoryMax`.
+#[derive(Clone, Copy, Debug)]
+pub struct Control {
+    /// The name of the setting in unit files.
+    pub name: &'static str,
+    /// The dbus property holding the value of the setting.
+    pub property: &'static str,
+    kind: ControlKind,
+}
+
+/// The settings offered by the resource control dialog.
+pub const CONTROLS: [Control; 6] = [
+    Control {
+        name: "CPUQuota",
+        property: "CPUQuotaPerSecUSec",
+        kind: ControlKind::Quota,
+    },
+    Control {
+        name: "CPUWeight",
+        property: "CPUWeight",
+        kind: ControlKind::Weight,
+    },
+    Control {
+        name: "MemoryHigh",
+        property: "MemoryHigh",
+        kind: ControlKind::Bytes,
+    },
+    Control {
+        name: "MemoryMax",
+        property: "MemoryMax",
+        kind: ControlKind::Bytes,
+    },
+    Control {
+        name: "TasksMax",
+        property: "TasksMax",
+        kind: ControlKind::Count,
+    },
+    Control {
+        name: "IOWeight",
+        property: "IOWeight",
+        kind: ControlKind::Weight,
+    },
+];
+
+impl Control {
+    /// Formats the current value of the setting the way it would be written in a unit file.
+    /// Weights which aren't set are left empty, while unlimited values read `infinity`.
+    pub fn format(&self, value: Option<u64>) -> String {
+        match (self.kind, value) {
+            (ControlKind::Weight, Some(value)) if value != u64::MAX => value.to_string(),
+            (ControlKind::Weight, _) => String::new(),
+            (_, None) | (_, Some(u64::MAX)) => String::from("infinity"),
+            (ControlKind::Quota, Some(value)) => format!("{}%", format_percent(value)),
+            (ControlKind::Bytes, Some(value)) => format_size(value),
+            (ControlKind::Count, Some(value)) => value.to_string(),
+        }
+    }
+
+    /// Parses a value written the way it would be in a unit file into the value of the dbus
+    /// property. An empty weight resets it to the default.
+    pub fn parse(&self, input: &str) -> Result<u64, String> {
+        let input = input.trim();
+        let invalid = || format!("Invalid value for {}: {:?}", self.name, input);
+        if input == "infinity" && self.kind != ControlKind::Weight {
+            return Ok(u64::MAX);
+        }
+
+        match self.kind {
+            ControlKind::Quota => input
+                .strip_suffix('%')
+                .and_then(|x| parse_percent(x.trim()))
+                .filter(|&x| x > 0)
+                .ok_or_else(invalid),
+            ControlKind::Bytes => parse_size(input).ok_or_else(invalid),
+            ControlKind::Count => input.parse::<u64>().map_err(|_| invalid()),
+            ControlKind::Weight if input.is_empty() => Ok(u64::MAX),
+            ControlKind::Weight => input
+                .parse::<u64>()
+                .ok()
+                .filter(|x| (1..=10_000).contains(x))
+                .ok_or_else(invalid),
+        }
+    }
+}
+
+/// Formats ten-thousandths of a percent, which is also the CPU time in microseconds per second,
+/// as a percentage without trailing zeros, such as `20.5`.
+fn format_percent(value: u64) -> String {
+    match value % 10_000 {
+        0 => (value / 10_000).to_string(),
+        fraction => {
+            let decimals = format!("{:04}", fraction);
+            format!("{}.{}", value / 10_000, decimals.trim_end_matches('0'))
+        }
+    }
+}
+
+/// Parses a percentage with up to four decimals, such as `20.5`, into ten-thousandths of a
+/// percent.
+fn parse_percent(input: &str) -> Option<u64> {
+    let (whole, fraction) = input.split_once('.').unwrap_or((input, ""));
+    let digits = |x: &str| x.chars().all(|x| x.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty())
+        || fraction.len() > 4
+        || !digits(whole)
+        || !digits(fraction)
+    {
+        return None;
+    }
+    let whole = match whole {
+        "" => 0,
+        whole => whole.parse::<u64>().ok()?,
+    };
+    let fraction = format!("{:0<4}", fraction).parse::<u64>().ok()?;
+    whole.checked_mul(10_000)?.checked_add(fraction)
+}
+
+/// Formats a byte count with the largest binary suffix which represents it exactly.
+fn format_size(bytes: u64) -> String {
+    let mut value = bytes;
+    for suffix in ["", "K", "M", "G", "T"].iter() {
+        if value == 0 || !value.is_multiple_of(1024) || *suffix == "T" {
+            return format!("{}{}", value, suffix);
+        }
+        value /= 1024;
+    }
+    unreachable!()
+}
+
+/// Parses a byte count which may use the K, M, G and T binary suffixes.
+fn parse_size(input: &str) -> Option<u64> {
+    let (number, multiplier) = match input.chars().last()? {
+        'K' => (&input[..input.len() - 1], 1 << 10),
+        'M' => (&input[..input.len() - 1], 1 << 20),
+        'G' => (&input[..input.len() - 1], 1 << 30),
+        'T' => (&input[..input.len() - 1], 1 << 40),
+        _ => (input, 1),
+    };
+    number.trim().parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn control(name: &str) -> Control {
+        *CONTROLS.iter().find(|x| x.name == name).unwrap()
+    }
+
+    #[test]
+    fn parses_sizes_with_suffixes() {
+        let memory = control("MemoryMax");
+        assert_eq!(memory.parse("512"), Ok(512));
+        assert_eq!(memory.parse("4K"), Ok(4 << 10));
+        assert_eq!(memory.parse("512M"), Ok(512 << 20));
+        assert_eq!(memory.parse(" 2G "), Ok(2 << 30));
+        assert_eq!(memory.parse("1T"), Ok(1 << 40));
+        assert!(memory.parse("").is_err());
+        assert!(memory.parse("2X").is_err());
+        assert!(memory.parse("-1M").is_err());
+    }
+
+    #[test]
+    fn parses_quotas_as_decimal_percentages() {
+        let quota = control("CPUQuota");
+        assert_eq!(quota.parse("20%"), Ok(200_000));
+        assert_eq!(quota.parse("20.5%"), Ok(205_000));
+        assert_eq!(quota.parse("0.25 %"), Ok(2_500));
+        assert_eq!(quota.parse(".5%"), Ok(5_000));
+        assert_eq!(quota.parse("150%"), Ok(1_500_000));
+        assert!(quota.parse("20").is_err());
+        assert!(quota.parse("0%").is_err());
+        assert!(quota.parse(".%").is_err());
+        assert!(quota.parse("1.23456%").is_err());
+        assert!(quota.parse("-5%").is_err());
+    }
+
+    #[test]
+    fn accepts_infinity_except_for_weights() {
+        assert_eq!(control("CPUQuota").parse("infinity"), Ok(u64::MAX));
+        assert_eq!(control("MemoryHigh").parse("infinity"), Ok(u64::MAX));
+        assert_eq!(control("TasksMax").parse("infinity"), Ok(u64::MAX));
+        assert!(control("CPUWeight").parse("infinity").is_err());
+    }
+
+    #[test]
+    fn rejects_values_which_overflow() {
+        assert!(control("CPUQuota").parse("18446744073709551615%").is_err());
+        assert!(control("CPUQuota").parse("1844674407370956%").is_err());
+        assert!(control("MemoryMax").parse("18446744073709551615K").is_err());
+        assert!(control("MemoryMax").parse("16777216T").is_err());
+        assert!(control("TasksMax").parse("18446744073709551616").is_err());
+    }
+
+    #[test]
+    fn bounds_weights_and_resets_empty_ones() {
+        let weight = control("IOWeight");
+        assert_eq!(weight.parse("1"), Ok(1));
+        assert_eq!(weight.parse("10000"), Ok(10_000));
+        assert!(weight.parse("0").is_err());
+        assert!(weight.parse("10001").is_err());
+        assert_eq!(weight.parse(""), Ok(u64::MAX));
+        assert_eq!(weight.parse("  "), Ok(u64::MAX));
+        assert_eq!(weight.format(None), "");
+        assert_eq!(weight.format(Some(u64::MAX)), "");
+    }
+
+    #[test]
+    fn formatted_values_parse_back() {
+        let values = [
+            ("CPUQuota", 200_000),
+            ("CPUQuota", 205_000),
+            ("CPUQuota", 1),
+            ("CPUWeight", 100),
+            ("MemoryHigh", 3 << 30),
+            ("MemoryMax", (1 << 20) + 1),
+            ("MemoryMax", 5 << 40),
+            ("TasksMax", 4915),
+        ];
+        for &(name, value) in values.iter() {
+            let control = control(name);
+            assert_eq!(control.parse(&control.format(Some(value))), Ok(value));
+        }
+        assert_eq!(control("CPUQuota").format(Some(205_000)), "20.5%");
+        assert_eq!(control("MemoryMax").format(Some(512 << 20)), "512M");
+        assert_eq!(control("MemoryMax").format(None), "infinity");
+    }
+}
@@ -0,0 +1,646 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use systemd::dbus::{DbusHandle, Properties};
+
+/// Bits of the `CapabilityBoundingSet` and `AmbientCapabilities` masks.
+const CAP_CHOWN: u64 = 1 << 0;
+const CAP_DAC_OVERRIDE: u64 = 1 << 1;
+const CAP_DAC_READ_SEARCH: u64 = 1 << 2;
+const CAP_FOWNER: u64 = 1 << 3;
+const CAP_FSETID: u64 = 1 << 4;
+const CAP_KILL: u64 = 1 << 5;
+const CAP_SETGID: u64 = 1 << 6;
+const CAP_SETUID: u64 = 1 << 7;
+const CAP_SETPCAP: u64 = 1 << 8;
+const CAP_NET_ADMIN: u64 = 1 << 12;
+const CAP_NET_RAW: u64 = 1 << 13;
+const CAP_SYS_MODULE: u64 = 1 << 16;
+const CAP_SYS_RAWIO: u64 = 1 << 17;
+const CAP_SYS_PTRACE: u64 = 1 << 19;
+const CAP_SYS_ADMIN: u64 = 1 << 21;
+const CAP_SYS_BOOT: u64 = 1 << 22;
+const CAP_SYS_TIME: u64 = 1 << 25;
+const CAP_MKNOD: u64 = 1 << 27;
+const CAP_SETFCAP: u64 = 1 << 31;
+const CAP_BPF: u64 = 1 << 39;
+
+/// Bits of the `RestrictNamespaces` mask, which lists the namespaces the service may create.
+const CLONE_NEWNS: u64 = 0x0002_0000;
+const CLONE_NEWCGROUP: u64 = 0x0200_0000;
+const CLONE_NEWUTS: u64 = 0x0400_0000;
+const CLONE_NEWIPC: u64 = 0x0800_0000;
+const CLONE_NEWUSER: u64 = 0x1000_0000;
+const CLONE_NEWPID: u64 = 0x2000_0000;
+const CLONE_NEWNET: u64 = 0x4000_0000;
+
+/// A sandboxing setting checked by the assessment. The assessment returns how exposed the
+/// service is, from `0.0` for fully protected to `1.0` for unprotected, or `None` when the
+/// manager doesn't report the setting.
+struct Check {
+    setting: &'static str,
+    description: &'static str,
+    /// How much the setting counts towards the overall exposure, relative to the other checks.
+    weight: u32,
+    assess: fn(&Properties) -> Option<f64>,
+    /// The line of a drop-in which would protect the service.
+    suggestion: &'static str,
+}
+
+/// Assesses a boolean setting which protects the service when enabled.
+fn enabled(properties: &Properties, name: &str) -> Option<f64> {
+    properties
+        .bool(name)
+        .map(|value| if value { 0.0 } else { 1.0 })
+}
+
+/// Assesses a setting which takes one of the given values, listed from the least to the most
+/// protective. Unknown values are assumed to be fully protective, as they are newer options.
+fn level(properties: &Properties, name: &str, values: &[&str]) -> Option<f64> {
+    let value = properties.string(name)?;
+    let steps = values.len().saturating_sub(1).max(1) as f64;
+    Some(match values.iter().position(|&x| x == value) {
+        Some(position) => 1.0 - position as f64 / steps,
+        None => 0.0,
+    })
+}
+
+/// Assesses whether the service keeps any of the given capabilities in its bounding set.
+fn capabilities(properties: &Properties, mask: u64) -> Option<f64> {
+    properties
+        .u64("CapabilityBoundingSet")
+        .map(|set| if set & mask != 0 { 1.0 } else { 0.0 })
+}
+
+/// Assesses whether the service may create any of the given namespaces.
+fn namespaces(properties: &Properties, mask: u64) -> Option<f64> {
+    properties
+        .u64("RestrictNamespaces")
+        .map(|allowed| if allowed & mask != 0 { 1.0 } else { 0.0 })
+}
+
+/// Assesses a `(bas)` list, where an allow list protects the service, a deny list only does
+/// so partially, and an empty deny list doesn't at all.
+fn restricted(properties: &Properties, name: &str) -> Option<f64> {
+    properties.list(name).map(|(allow, items)| {
+        if allow {
+            0.0
+        } else if items.is_empty() {
+            1.0
+        } else {
+            0.5
+        }
+    })
+}
+
+const CHECKS: [Check; 33] = [
+    Check {
+        setting: "User=/DynamicUser=",
+        description: "Service runs as root",
+        weight: 2000,
+        assess: |properties| {
+            if properties.bool("DynamicUser") == Some(true) {
+                return Some(0.0);
+            }
+            let user = properties.string("User")?;
+            Some(if user.is_empty() || user == "root" || user == "0" {
+                1.0
+            } else {
+                0.0
+            })
+        },
+        suggestion: "DynamicUser=yes",
+    },
+    Check {
+        setting: "NoNewPrivileges=",
+        description: "Service processes may acquire new privileges",
+        weight: 1000,
+        assess: |properties| enabled(properties, "NoNewPrivileges"),
+        suggestion: "NoNewPrivileges=yes",
+    },
+    Check {
+        setting: "ProtectSystem=",
+        description: "Service has write access to the OS file hierarchy",
+        weight: 1000,
+        assess: |properties| {
+            level(
+                properties,
+                "ProtectSystem",
+                &["no", "yes", "full", "strict"],
+            )
+        },
+        suggestion: "ProtectSystem=strict",
+    },
+    Check {
+        setting: "ProtectHome=",
+        description: "Service has access to home directories",
+        weight: 1000,
+        assess: |properties| {
+            level(
+                properties,
+                "ProtectHome",
+                &["no", "read-only", "tmpfs", "yes"],
+            )
+        },
+        suggestion: "ProtectHome=yes",
+    },
+    Check {
+        setting: "PrivateTmp=",
+        description: "Service has access to other software's temporary files",
+        weight: 1000,
+        assess: |properties| enabled(properties, "PrivateTmp"),
+        suggestion: "PrivateTmp=yes",
+    },
+    Check {
+        setting: "PrivateDevices=",
+        description: "Service has access to hardware devices",
+        weight: 1000,
+        assess: |properties| enabled(properties, "PrivateDevices"),
+        suggestion: "PrivateDevices=yes",
+    },
+    Check {
+        setting: "DevicePolicy=",
+        description: "Service may create and access every device node",
+        weight: 1000,
+        assess: |properties| level(properties, "DevicePolicy", &["auto", "closed", "strict"]),
+        suggestion: "DevicePolicy=closed",
+    },
+    Check {
+        setting: "PrivateNetwork=",
+        description: "Service has access to the host's network",
+        weight: 2500,
+        assess: |properties| enabled(properties, "PrivateNetwork"),
+        suggestion: "PrivateNetwork=yes",
+    },
+    Check {
+        setting: "PrivateUsers=",
+        description: "Service has access to other users",
+        weight: 1000,
+        assess: |properties| enabled(properties, "PrivateUsers"),
+        suggestion: "PrivateUsers=yes",
+    },
+    Check {
+        setting: "ProtectKernelTunables=",
+        description: "Service may alter kernel tunables",
+        weight: 1000,
+        assess: |properties| enabled(properties, "ProtectKernelTunables"),
+        suggestion: "ProtectKernelTunables=yes",
+    },
+    Check {
+        setting: "ProtectKernelModules=",
+        description: "Service may load or read kernel modules",
+        weight: 1000,
+        assess: |properties| enabled(properties, "ProtectKernelModules"),
+        suggestion: "ProtectKernelModules=yes",
+    },
+    Check {
+        setting: "ProtectKernelLogs=",
+        description: "Service may read from or write to the kernel log ring buffer",
+        weight: 1000,
+        assess: |properties| enabled(properties, "ProtectKernelLogs"),
+        suggestion: "ProtectKernelLogs=yes",
+    },
+    Check {
+        setting: "ProtectControlGroups=",
+        description: "Service may modify the control group file system",
+        weight: 1000,
+        assess: |properties| enabled(properties, "ProtectControlGroups"),
+        suggestion: "ProtectControlGroups=yes",
+    },
+    Check {
+        setting: "ProtectClock=",
+        description: "Service may write to the hardware clock or system clock",
+        weight: 1000,
+        assess: |properties| enabled(properties, "ProtectClock"),
+        suggestion: "ProtectClock=yes",
+    },
+    Check {
+        setting: "ProtectHostname=",
+        description: "Service may change the system host name",
+        weight: 50,
+        assess: |properties| enabled(properties, "ProtectHostname"),
+        suggestion: "ProtectHostname=yes",
+    },
+    Check {
+        setting: "ProtectProc=",
+        description: "Service has full access to the process tree of /proc",
+        weight: 1000,
+        assess: |properties| {
+            level(
+                properties,
+                "ProtectProc",
+                &["default", "ptraceable", "invisible"],
+            )
+        },
+        suggestion: "ProtectProc=invisible",
+    },
+    Check {
+        setting: "RestrictSUIDSGID=",
+        description: "Service may create SUID/SGID files",
+        weight: 1000,
+        assess: |properties| enabled(properties, "RestrictSUIDSGID"),
+        suggestion: "RestrictSUIDSGID=yes",
+    },
+    Check {
+        setting: "RestrictRealtime=",
+        description: "Service may acquire realtime scheduling",
+        weight: 500,
+        assess: |properties| enabled(properties, "RestrictRealtime"),
+        suggestion: "RestrictRealtime=yes",
+    },
+    Check {
+        setting: "LockPersonality=",
+        description: "Service may change the ABI personality",
+        weight: 100,
+        assess: |properties| enabled(properties, "LockPersonality"),
+        suggestion: "LockPersonality=yes",
+    },
+    Check {
+        setting: "MemoryDenyWriteExecute=",
+        description: "Service may create writable executable memory mappings",
+        weight: 100,
+        assess: |properties| enabled(properties, "MemoryDenyWriteExecute"),
+        suggestion: "MemoryDenyWriteExecute=yes",
+    },
+    Check {
+        setting: "RemoveIPC=",
+        description: "Service user may leave SysV IPC objects around",
+        weight: 100,
+        assess: |properties| enabled(properties, "RemoveIPC"),
+        suggestion: "RemoveIPC=yes",
+    },
+    Check {
+        setting: "RestrictNamespaces=~user",
+        description: "Service may create user namespaces",
+        weight: 1500,
+        assess: |properties| namespaces(properties, CLONE_NEWUSER),
+        suggestion: "RestrictNamespaces=~user",
+    },
+    Check {
+        setting: "RestrictNamespaces=",
+        description: "Service may create mount, network, process, IPC, cgroup or UTS namespaces",
+        weight: 1000,
+        assess: |properties| {
+            namespaces(
+                properties,
+                CLONE_NEWNS
+                    | CLONE_NEWNET
+                    | CLONE_NEWPID
+                    | CLONE_NEWIPC
+                    | CLONE_NEWCGROUP
+                    | CLONE_NEWUTS,
+            )
+        },
+        suggestion: "RestrictNamespaces=yes",
+    },
+    Check {
+        setting: "RestrictAddressFamilies=",
+        description: "Service may allocate sockets of any address family",
+        weight: 1500,
+        assess: |properties| restricted(properties, "RestrictAddressFamilies"),
+        suggestion: "RestrictAddressFamilies=AF_UNIX AF_INET AF_INET6",
+    },
+    Check {
+        setting: "SystemCallFilter=",
+        description: "Service may issue any system call",
+        weight: 3000,
+        assess: |properties| restricted(properties, "SystemCallFilter"),
+        suggestion: "SystemCallFilter=@system-service",
+    },
+    Check {
+        setting: "SystemCallArchitectures=",
+        description: "Service may execute system calls of any ABI",
+        weight: 1000,
+        assess: |properties| {
+            properties.get("SystemCallArchitectures")?;
+            Some(
+                if properties.strings("SystemCallArchitectures").is_empty() {
+                    1.0
+                } else {
+                    0.0
+                },
+            )
+        },
+        suggestion: "SystemCallArchitectures=native",
+    },
+    Check {
+        setting: "CapabilityBoundingSet=~CAP_SYS_ADMIN",
+        description: "Service has administrator privileges",
+        weight: 1500,
+        assess: |properties| capabilities(properties, CAP_SYS_ADMIN),
+        suggestion: "CapabilityBoundingSet=~CAP_SYS_ADMIN",
+    },
+    Check {
+        setting: "CapabilityBoundingSet=~CAP_SET(UID|GID|PCAP|FCAP)",
+        description: "Service may change its user, group or capabilities",
+        weight: 1500,
+        assess: |properties| {
+            capabilities(
+                properties,
+                CAP_SETUID | CAP_SETGID | CAP_SETPCAP | CAP_SETFCAP,
+            )
+        },
+        suggestion: "CapabilityBoundingSet=~CAP_SETUID CAP_SETGID CAP_SETPCAP CAP_SETFCAP",
+    },
+    Check {
+        setting: "CapabilityBoundingSet=~CAP_SYS_(PTRACE|MODULE|RAWIO|BOOT|TIME)",
+        description:
+            "Service may trace processes, load modules, access raw I/O, reboot or set the clock",
+        weight: 1500,
+        assess: |properties| {
+            capabilities(
+                properties,
+                CAP_SYS_PTRACE | CAP_SYS_MODULE | CAP_SYS_RAWIO | CAP_SYS_BOOT | CAP_SYS_TIME,
+            )
+        },
+        suggestion: "CapabilityBoundingSet=~CAP_SYS_PTRACE CAP_SYS_MODULE CAP_SYS_RAWIO \
+                     CAP_SYS_BOOT CAP_SYS_TIME",
+    },
+    Check {
+        setting: "CapabilityBoundingSet=~CAP_NET_(ADMIN|RAW)",
+        description: "Service may configure the network or use raw sockets",
+        weight: 1500,
+        assess: |properties| capabilities(properties, CAP_NET_ADMIN | CAP_NET_RAW),
+        suggestion: "CapabilityBoundingSet=~CAP_NET_ADMIN CAP_NET_RAW",
+    },
+    Check {
+        setting: "CapabilityBoundingSet=~CAP_(CHOWN|FSETID|DAC_*|FOWNER|MKNOD|KILL|BPF)",
+        description: "Service may override file permissions, create device nodes, signal any \
+                      process or load BPF programs",
+        weight: 1000,
+        assess: |properties| {
+            capabilities(
+                properties,
+                CAP_CHOWN
+                    | CAP_FSETID
+                    | CAP_DAC_OVERRIDE
+                    | CAP_DAC_READ_SEARCH
+                    | CAP_FOWNER
+                    | CAP_MKNOD
+                    | CAP_KILL
+                    | CAP_BPF,
+            )
+        },
+        suggestion: "CapabilityBoundingSet=~CAP_CHOWN CAP_FSETID CAP_DAC_OVERRIDE \
+                     CAP_DAC_READ_SEARCH CAP_FOWNER CAP_MKNOD CAP_KILL CAP_BPF",
+    },
+    Check {
+        setting: "AmbientCapabilities=",
+        description: "Service process receives ambient capabilities",
+        weight: 500,
+        assess: |properties| {
+            properties
+                .u64("AmbientCapabilities")
+                .map(|set| if set != 0 { 1.0 } else { 0.0 })
+        },
+        suggestion: "AmbientCapabilities=",
+    },
+    Check {
+        setting: "UMask=",
+        description: "Files created by the service are readable or writable by everyone",
+        weight: 100,
+        assess: |properties| {
+            properties.u64("UMask").map(|umask| {
+                if umask & 0o002 == 0 {
+                    1.0
+                } else if umask & 0o004 == 0 {
+                    0.5
+                } else {
+                    0.0
+                }
+            })
+        },
+        suggestion: "UMask=0077",
+    },
+];
+
+/// The result of a single check of a service.
+#[derive(Clone, Debug)]
+pub struct Finding {
+    pub setting: &'static str,
+    pub description: &'static str,
+    /// How much the setting adds to the overall exposure of the service, out of 10.
+    pub exposure: f64,
+    pub suggestion: &'static str,
+}
+
+impl Finding {
+    /// Whether the service is fully protected by the setting.
+    pub fn passed(&self) -> bool {
+        self.exposure == 0.0
+    }
+}
+
+/// How exposed a service is, like `systemd-analyze security`. The exposure goes from `0.0` for
+/// a fully sandboxed service to `10.0` for a service without any protection.
+#[derive(Clone, Debug)]
+pub struct Assessment {
+    pub unit: String,
+    pub exposure: f64,
+    pub findings: Vec<Finding>,
+}
+
+impl Assessment {
+    /// Checks every sandboxing setting of a service, given the properties of its `Service`
+    /// interface. Settings unknown to the manager are left out of the score.
+    pub fn new(unit: String, properties: &Properties) -> Assessment {
+        let results: Vec<(&Check, f64)> = CHECKS
+            .iter()
+            .filter_map(|check| (check.assess)(properties).map(|badness| (check, badness)))
+            .collect();
+        let total: u32 = results.iter().map(|&(check, _)| check.weight).sum();
+        let findings: Vec<Finding> = results
+            .into_iter()
+            .map(|(check, badness)| Finding {
+                setting: check.setting,
+                description: check.description,
+                exposure: if total == 0 {
+                    0.0
+                } else {
+                    10.0 * badness * f64::from(check.weight) / f64::from(total)
+                },
+                suggestion: check.suggestion,
+            })
+            .collect();
+        Assessment {
+            unit,
+            exposure: findings.iter().map(|x| x.exposure).sum(),
+            findings,
+        }
+    }
+
+    /// Describes the exposure with the same words as `systemd-analyze security`.
+    pub fn level(&self) -> &'static str {
+        match self.exposure {
+            x if x <= 1.0 => "PERFECT",
+            x if x <= 2.0 => "SAFE",
+            x if x <= 4.0 => "OK",
+            x if x <= 7.0 => "MEDIUM",
+            x if x <= 9.0 => "EXPOSED",
+            _ => "UNSAFE",
+        }
+    }
+}
+
+/// Assesses every loaded service, from the most exposed to the least exposed.
+pub fn assess_services(handle: &DbusHandle) -> Result<Vec<Assessment>, String> {
+    let units = handle
+        .list_units()
+        .map_err(|error| format!("Unable to list the units: {:?}", error))?;
+    let mut assessments = Vec::new();
+    for unit in units.into_iter().filter(|x| x.name.ends_with(".service")) {
+        let properties = handle
+            .get_unit_properties(&unit.name, "")
+            .map_err(|error| {
+                format!("Unable to get the properties of {}: {:?}", unit.name, error)
+            })?;
+        // Units which are only referenced by others have no settings to assess.
+        if properties.string("LoadState").as_deref() != Some("loaded") {
+            continue;
+        }
+        assessments.push(Assessment::new(unit.name, &properties));
+    }
+    assessments.sort_by(|a, b| {
+        b.exposure
+            .partial_cmp(&a.exposure)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.unit.cmp(&b.unit))
+    });
+    Ok(assessments)
+}
+
+/// Writes the chosen suggestions as a drop-in for the `[Service]` section.
+pub fn drop_in(suggestions: &[&str]) -> String {
+    let mut output = String::from("[Service]\n");
+    for suggestion in suggestions {
+        output.push_str(suggestion);
+        output.push('\n');
+    }
+    output
+}
+
+/// Where the drop-in holding the sandboxing settings of a unit is written. Drop-ins of the user
+/// manager go within `$XDG_CONFIG_HOME`, falling back to `~/.config`.
+pub fn drop_in_path(unit: &str, user: bool) -> PathBuf {
+    let directory = if user {
+        env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|x| x.is_absolute())
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .unwrap_or_else(env::temp_dir)
+            .join("systemd/user")
+    } else {
+        PathBuf::from("/etc/systemd/system")
+    };
+    directory.join(format!("{}.d", unit)).join("security.conf")
+}
+
+/// Writes the drop-in of a unit, replacing any earlier one. The file is written under a
+/// temporary name first, so that the manager never reads a partial drop-in.
+pub fn write_drop_in(unit: &str, user: bool, contents: &str) -> io::Result<PathBuf> {
+    let path = drop_in_path(unit, user);
+    let directory = path.parent().unwrap();
+    fs::create_dir_all(directory)?;
+    let temporary = directory.join(".security.conf.tmp");
+    fs::write(&temporary, contents)?;
+    fs::rename(&temporary, &path)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate dbus;
+    use self::dbus::MessageItem;
+    use super::*;
+
+    fn properties(values: Vec<(&str, MessageItem)>) -> Properties {
+        let mut properties = Properties::default();
+        for (name, value) in values {
+            properties.insert(name, value);
+        }
+        properties
+    }
+
+    fn assess(values: Vec<(&str, MessageItem)>) -> Assessment {
+        Assessment::new(String::from("test.service"), &properties(values))
+    }
+
+    fn exposure(exposure: f64) -> Assessment {
+        Assessment {
+            unit: String::from("test.service"),
+            exposure,
+            findings: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn unknown_settings_are_left_out() {
+        let assessment = assess(Vec::new());
+        assert!(assessment.findings.is_empty());
+        assert_eq!(assessment.exposure, 0.0);
+        assert_eq!(assessment.level(), "PERFECT");
+    }
+
+    #[test]
+    fn root_service_without_protection_is_unsafe() {
+        let assessment = assess(vec![("User", MessageItem::Str(String::new()))]);
+        assert_eq!(assessment.findings.len(), 1);
+        assert_eq!(assessment.exposure, 10.0);
+        assert_eq!(assessment.level(), "UNSAFE");
+    }
+
+    #[test]
+    fn checks_are_weighted() {
+        let assessment = assess(vec![
+            ("User", MessageItem::Str(String::from("root"))),
+            ("NoNewPrivileges", MessageItem::Bool(true)),
+        ]);
+        assert!((assessment.exposure - 20.0 / 3.0).abs() < 1e-9);
+        assert_eq!(assessment.level(), "MEDIUM");
+        let passed: Vec<&str> = assessment
+            .findings
+            .iter()
+            .filter(|x| x.passed())
+            .map(|x| x.setting)
+            .collect();
+        assert_eq!(passed, ["NoNewPrivileges="]);
+
+        let assessment = assess(vec![
+            ("DynamicUser", MessageItem::Bool(true)),
+            ("NoNewPrivileges", MessageItem::Bool(false)),
+        ]);
+        assert!((assessment.exposure - 10.0 / 3.0).abs() < 1e-9);
+        assert_eq!(assessment.level(), "OK");
+    }
+
+    #[test]
+    fn levels_are_scored_by_position() {
+        let score = |value: &str| {
+            assess(vec![("ProtectSystem", MessageItem::Str(value.to_owned()))]).exposure
+        };
+        assert_eq!(score("no"), 10.0);
+        assert!((score("full") - 10.0 / 3.0).abs() < 1e-9);
+        assert_eq!(score("strict"), 0.0);
+        assert_eq!(score("unknown"), 0.0);
+    }
+
+    #[test]
+    fn levels_match_systemd_analyze_thresholds() {
+        let levels = [
+            (0.0, "PERFECT"),
+            (1.0, "PERFECT"),
+            (1.1, "SAFE"),
+            (2.0, "SAFE"),
+            (4.0, "OK"),
+            (4.1, "MEDIUM"),
+            (7.0, "MEDIUM"),
+            (9.0, "EXPOSED"),
+            (9.1, "UNSAFE"),
+            (10.0, "UNSAFE"),
+        ];
+        for &(value, level) in levels.iter() {
+            assert_eq!(exposure(value).level(), level, "{}", value);
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use systemd::dbus::DbusHandle;
+
+/// A loaded socket unit, like the rows of `systemctl list-sockets`.
+#[derive(Clone, Debug)]
+pub struct Socket {
+    pub name: String,
+    /// The type and address of every socket it listens on, such as `("Stream", "/run/foo")`.
+    /// The types are `Stream`, `Datagram`, `SequentialPacket`, `FIFO`, `Special`, `Netlink`,
+    /// `MessageQueue` and `USBFunction`.
+    pub listen: Vec<(String, String)>,
+    /// The number of connections accepted so far, for sockets with `Accept=yes`.
+    pub accepted: Option<u64>,
+    /// The number of connections currently open.
+    pub connections: Option<u64>,
+    /// The number of connections refused, which older versions of systemd don't report.
+    pub refused: Option<u64>,
+    /// The units started by the socket, usually the service of the same name.
+    pub triggers: Vec<String>,
+}
+
+/// Collects every loaded socket unit, ordered by their first listen address like
+/// `systemctl list-sockets`.
+pub fn list_sockets(handle: &DbusHandle) -> Result<Vec<Socket>, String> {
+    let units = handle
+        .list_units()
+        .map_err(|error| format!("Unable to list the units: {:?}", error))?;
+
+    let mut sockets = Vec::new();
+    for unit in units.into_iter().filter(|x| x.name.ends_with(".socket")) {
+        let properties = handle
+            .get_unit_properties(&unit.name, "")
+            .map_err(|error| {
+                format!("Unable to get the properties of {}: {:?}", unit.name, error)
+            })?;
+        sockets.push(Socket {
+            name: unit.name,
+            listen: properties.string_pairs("Listen"),
+            accepted: properties.u64("NAccepted"),
+            connections: properties.u64("NConnections"),
+            refused: properties.u64("NRefused"),
+            triggers: properties.strings("Triggers"),
+        });
+    }
+    sockets.sort_by(|a, b| {
+        let address = |socket: &Socket| socket.listen.first().map(|x| x.1.clone());
+        address(a)
+            .cmp(&address(b))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    Ok(sockets)
+}
@@ -0,0 +1,138 @@
+extern crate libc;
+use systemd::dbus::Properties;
+use systemd::timespan;
+
+/// Collects the information `systemctl status` would display for a unit as a list of
+/// `(field, value)` pairs, in the order they should be displayed. Fields which systemd doesn't
+/// track for the unit are left out.
+pub fn status_fields(properties: &Properties) -> Vec<(&'static str, String)> {
+    let mut fields = Vec::new();
+
+    if let Some(description) = properties.string("Description") {
+        fields.push(("Description", description));
+    }
+
+    let load_state = properties.string("LoadState").unwrap_or_default();
+    let loaded = match (
+        properties.string("FragmentPath"),
+        properties.string("UnitFileState"),
+    ) {
+        (Some(ref path), Some(ref state)) if !path.is_empty() && !state.is_empty() => {
+            format!("{} ({}; {})", load_state, path, state)
+        }
+        (Some(ref path), _) if !path.is_empty() => format!("{} ({})", load_state, path),
+        _ => load_state,
+    };
+    fields.push(("Loaded", loaded));
+
+    let active_state = properties.string("ActiveState").unwrap_or_default();
+    let mut active = format!(
+        "{} ({})",
+        active_state,
+        properties.string("SubState").unwrap_or_default()
+    );
+    match properties.u64("ActiveEnterTimestamp") {
+        Some(since) if since != 0 && active_state == "active" => {
+            active.push_str(&format!(" since {}", format_timestamp(since)));
+        }
+        _ => (),
+    }
+    fields.push(("Active", active));
+
+    if let Some(result) = properties.string("Result") {
+        fields.push(("Result", result));
+    }
+
+    match properties.u64("MainPID") {
+        Some(pid) if pid != 0 => fields.push(("Main PID", pid.to_string())),
+        // Once the main process is gone, how it ended is shown instead.
+        _ => match (
+            properties.u64("ExecMainPID"),
+            properties.i64("ExecMainCode"),
+        ) {
+            (Some(pid), Some(code)) if pid != 0 && code != 0 => {
+                let code = match code {
+                    1 => "exited",
+                    2 => "killed",
+                    3 => "dumped",
+                    _ => "unknown",
+                };
+                let status = properties.i64("ExecMainStatus").unwrap_or(0);
+                fields.push((
+                    "Main PID",
+                    format!("{} (code={}, status={})", pid, code, status),
+                ));
+            }
+            _ => (),
+        },
+    }
+
+    if let Some(tasks) = properties.counter("TasksCurrent") {
+        let tasks = match properties.counter("TasksMax") {
+            Some(max) => format!("{} (limit: {})", tasks, max),
+            None => tasks.to_string(),
+        };
+        fields.push(("Tasks", tasks));
+    }
+
+    if let Some(memory) = properties.counter("MemoryCurrent") {
+        let memory = match properties.counter("MemoryPeak") {
+            Some(peak) => format!("{} (peak: {})", format_bytes(memory), format_bytes(peak)),
+            None => format_bytes(memory),
+        };
+        fields.push(("Memory", memory));
+    }
+
+    if let Some(nsec) = properties.counter("CPUUsageNSec") {
+        fields.push(("CPU", timespan::format(nsec / 1000)));
+    }
+
+    if let Some(restarts) = properties.u64("NRestarts") {
+        fields.push(("Restarts", restarts.to_string()));
+    }
+
+    let triggered_by = properties.strings("TriggeredBy");
+    if !triggered_by.is_empty() {
+        fields.push(("Triggered By", triggered_by.join(", ")));
+    }
+
+    let triggers = properties.strings("Triggers");
+    if !triggers.is_empty() {
+        fields.push(("Triggers", triggers.join(", ")));
+    }
+
+    fields
+}
+
+/// Formats a byte count the way systemd does, such as `1.5M`.
+pub fn format_bytes(bytes: u64) -> String {
+    const SUFFIXES: [&str; 6] = ["K", "M", "G", "T", "P", "E"];
+    if bytes < 1024 {
+        return format!("{}B", bytes);
+    }
+    let mut value = bytes as f64 / 1024f64;
+    let mut suffix = 0;
+    while value >= 1024f64 && suffix < SUFFIXES.len() - 1 {
+        value /= 1024f64;
+        suffix += 1;
+    }
+    format!("{:.1}{}", value, SUFFIXES[suffix])
+}
+
+/// Formats a realtime timestamp in microseconds since the epoch as a local date and time.
+pub fn format_timestamp(usec: u64) -> String {
+    let time = (usec / 1_000_000) as libc::time_t;
+    let mut tm: libc::tm = unsafe { ::std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        return String::from("n/a");
+    }
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
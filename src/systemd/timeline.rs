@@ -0,0 +1,301 @@
+use systemd::dbus::DbusHandle;
+use systemd::graph::escape_xml;
+use systemd::timespan;
+
+/// Dimensions used when drawing the timeline. The horizontal scale is multiplied by the zoom.
+pub const ROW_HEIGHT: f64 = 16.0;
+const BAR_HEIGHT: f64 = 12.0;
+const PIXELS_PER_SECOND: f64 = 100.0;
+const AXIS_HEIGHT: f64 = 20.0;
+const MARGIN: f64 = 10.0;
+/// Room left after the end of the boot for the names of the last units.
+const LABEL_SPACE: f64 = 300.0;
+
+/// What a unit was doing during a part of its bar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// One of the phases of the boot itself, such as the kernel.
+    Boot,
+    Activating,
+    Active,
+    Deactivating,
+}
+
+impl Phase {
+    /// The color used to draw the phase, as an SVG color and as an RGB triplet, following the
+    /// colors of `systemd-analyze plot`.
+    pub fn color(self) -> (&'static str, (f64, f64, f64)) {
+        match self {
+            Phase::Boot => ("#8ab4d8", (0.54, 0.71, 0.85)),
+            Phase::Activating => ("#ff4d4d", (1.0, 0.3, 0.3)),
+            Phase::Active => ("#ffd5d5", (1.0, 0.84, 0.84)),
+            Phase::Deactivating => ("#b0b0b0", (0.69, 0.69, 0.69)),
+        }
+    }
+}
+
+/// A row of the timeline. Timestamps are in microseconds since the kernel started, and are
+/// `None` when the unit didn't reach that state.
+#[derive(Clone, Debug)]
+pub struct Bar {
+    pub label: String,
+    pub phase: Phase,
+    pub activating: u64,
+    pub activated: Option<u64>,
+    pub deactivating: Option<u64>,
+    pub deactivated: Option<u64>,
+}
+
+impl Bar {
+    /// Splits the bar into the parts drawn with a different color, as `(phase, start, end)`.
+    /// Units which are still active extend until the end of the timeline.
+    pub fn segments(&self, end: u64) -> Vec<(Phase, u64, u64)> {
+        if self.phase == Phase::Boot {
+            return vec![(Phase::Boot, self.activating, self.activated.unwrap_or(end))];
+        }
+
+        let mut segments = Vec::new();
+        let activated = self.activated.unwrap_or(end).min(end);
+        segments.push((Phase::Activating, self.activating, activated));
+        if self.activated.is_some() {
+            let deactivating = self.deactivating.unwrap_or(end).min(end);
+            segments.push((Phase::Active, activated, deactivating));
+            if self.deactivating.is_some() {
+                let deactivated = self.deactivated.unwrap_or(end).min(end);
+                segments.push((Phase::Deactivating, deactivating, deactivated));
+            }
+        }
+        segments.retain(|&(_, start, end)| end > start);
+        segments
+    }
+
+    /// Describes when the unit changed state, for the tooltips of the timeline.
+    pub fn describe(&self) -> String {
+        let mut description = self.label.clone();
+        let mut line = |name: &str, time: Option<u64>| {
+            if let Some(time) = time {
+                description.push_str(&format!("\n{}: @{}", name, timespan::format(time)));
+            }
+        };
+        match self.phase {
+            Phase::Boot => {
+                line("Started", Some(self.activating));
+                line("Finished", self.activated);
+            }
+            _ => {
+                line("Activating", Some(self.activating));
+                line("Active", self.activated);
+                line("Deactivating", self.deactivating);
+                line("Inactive", self.deactivated);
+            }
+        }
+        if let Some(activated) = self.activated {
+            description.push_str(&format!(
+                "\nTook: {}",
+                timespan::format(activated.saturating_sub(self.activating))
+            ));
+        }
+        description
+    }
+}
+
+/// The activation of every unit during the boot, similar to the output of
+/// `systemd-analyze plot`.
+#[derive(Clone, Debug, Default)]
+pub struct Timeline {
+    pub bars: Vec<Bar>,
+    /// When the timeline ends, in microseconds since the kernel started.
+    pub end: u64,
+}
+
+impl Timeline {
+    /// Collects the timestamps of the boot phases and of every loaded unit which started
+    /// before the boot finished, ordered by the time they started activating.
+    pub fn build(handle: &DbusHandle) -> Result<Timeline, String> {
+        let manager = handle
+            .get_manager_properties()
+            .map_err(|error| format!("Unable to get the properties of the manager: {:?}", error))?;
+        let timestamp = |name: &str| manager.u64(name).filter(|&x| x != 0);
+        let userspace = timestamp("UserspaceTimestampMonotonic").unwrap_or(0);
+        let finish = timestamp("FinishTimestampMonotonic");
+
+        let mut bars = Vec::new();
+        let mut boot_phase = |label: &str, start: u64, end: Option<u64>| {
+            bars.push(Bar {
+                label: label.to_owned(),
+                phase: Phase::Boot,
+                activating: start,
+                activated: end,
+                deactivating: None,
+                deactivated: None,
+            })
+        };
+        // User managers don't record the phases before they were started.
+        match (
+            timestamp("KernelTimestamp"),
+            timestamp("InitRDTimestampMonotonic"),
+        ) {
+            (Some(_), Some(initrd)) => {
+                boot_phase("kernel", 0, Some(initrd));
+                boot_phase("initrd", initrd, Some(userspace));
+            }
+            (Some(_), None) => boot_phase("kernel", 0, Some(userspace)),
+            (None, _) => (),
+        }
+        boot_phase("systemd", userspace, finish);
+
+        let units = handle
+            .list_units()
+            .map_err(|error| format!("Unable to list the units: {:?}", error))?;
+        let mut unit_bars = Vec::new();
+        for unit in units {
+            let properties = handle
+                .get_unit_properties(&unit.name, "org.freedesktop.systemd1.Unit")
+                .map_err(|error| {
+                    format!("Unable to get the properties of {}: {:?}", unit.name, error)
+                })?;
+            let timestamp = |name: &str| properties.u64(name).filter(|&x| x != 0);
+            let activated = timestamp("ActiveEnterTimestampMonotonic");
+            let activating = match timestamp("InactiveExitTimestampMonotonic").or(activated) {
+                Some(activating) => activating,
+                None => continue,
+            };
+            if finish.is_some_and(|finish| activating > finish) {
+                continue;
+            }
+            // Timestamps of an earlier activation are left over once a unit starts again.
+            let after = |time: Option<u64>| time.filter(|&x| x >= activating);
+            unit_bars.push(Bar {
+                label: unit.name,
+                phase: Phase::Active,
+                activating,
+                activated: after(activated),
+                deactivating: after(timestamp("ActiveExitTimestampMonotonic")),
+                deactivated: after(timestamp("InactiveEnterTimestampMonotonic")),
+            });
+        }
+        unit_bars.sort_by(|a, b| (a.activating, &a.label).cmp(&(b.activating, &b.label)));
+        bars.extend(unit_bars);
+
+        let end = bars
+            .iter()
+            .flat_map(|x| x.activated.into_iter().chain(Some(x.activating)))
+            .chain(finish)
+            .max()
+            .unwrap_or(0);
+        Ok(Timeline { bars, end })
+    }
+
+    /// The horizontal position of a timestamp at the given zoom level.
+    pub fn x(&self, usec: u64, zoom: f64) -> f64 {
+        MARGIN + usec as f64 / 1_000_000.0 * PIXELS_PER_SECOND * zoom
+    }
+
+    /// The vertical position of the top of a row.
+    pub fn y(&self, row: usize) -> f64 {
+        MARGIN + AXIS_HEIGHT + row as f64 * ROW_HEIGHT
+    }
+
+    pub fn width(&self, zoom: f64) -> f64 {
+        self.x(self.end, zoom) + LABEL_SPACE + MARGIN
+    }
+
+    pub fn height(&self) -> f64 {
+        self.y(self.bars.len()) + MARGIN
+    }
+
+    /// The bar of the row found at the given vertical position, if any.
+    pub fn bar_at(&self, y: f64) -> Option<&Bar> {
+        let offset = y - self.y(0);
+        if offset < 0.0 {
+            return None;
+        }
+        self.bars.get((offset / ROW_HEIGHT) as usize)
+    }
+
+    /// The interval between the ticks of the time axis, in seconds, keeping the labels of
+    /// neighbouring ticks apart.
+    pub fn tick_interval(&self, zoom: f64) -> u64 {
+        let pixels = PIXELS_PER_SECOND * zoom;
+        [1, 2, 5, 10, 30, 60]
+            .iter()
+            .cloned()
+            .find(|&seconds| seconds as f64 * pixels >= 50.0)
+            .unwrap_or(120)
+    }
+
+    /// Returns the rectangle of each segment of the bar in the given row, with its phase.
+    pub fn segment_rects(&self, row: usize, zoom: f64) -> Vec<(Phase, f64, f64, f64, f64)> {
+        let y = self.y(row) + (ROW_HEIGHT - BAR_HEIGHT) / 2.0;
+        self.bars[row]
+            .segments(self.end)
+            .into_iter()
+            .map(|(phase, start, end)| {
+                let x = self.x(start, zoom);
+                // Instant activations are still drawn, one pixel wide.
+                let width = (self.x(end, zoom) - x).max(1.0);
+                (phase, x, y, width, BAR_HEIGHT)
+            })
+            .collect()
+    }
+
+    /// Where the name of the unit in the given row is drawn, just after it became active.
+    pub fn label_position(&self, row: usize, zoom: f64) -> (f64, f64) {
+        let bar = &self.bars[row];
+        let start = bar.activated.unwrap_or(bar.activating).min(self.end);
+        (self.x(start, zoom) + 4.0, self.y(row) + ROW_HEIGHT - 4.0)
+    }
+
+    /// Renders the timeline as a standalone SVG document.
+    pub fn to_svg(&self, zoom: f64) -> String {
+        let width = self.width(zoom);
+        let height = self.height();
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" \
+             viewBox=\"0 0 {0} {1}\" font-family=\"sans-serif\" font-size=\"11\">\n\
+             <rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n",
+            width, height
+        );
+
+        let interval = self.tick_interval(zoom);
+        let mut second = 0;
+        while second * 1_000_000 <= self.end {
+            let x = self.x(second * 1_000_000, zoom);
+            svg.push_str(&format!(
+                "<line x1=\"{0}\" y1=\"{1}\" x2=\"{0}\" y2=\"{2}\" stroke=\"#dddddd\"/>\n\
+                 <text x=\"{3}\" y=\"{4}\">{5}s</text>\n",
+                x,
+                self.y(0),
+                height - MARGIN,
+                x + 2.0,
+                MARGIN + AXIS_HEIGHT - 6.0,
+                second
+            ));
+            second += interval;
+        }
+
+        for (row, bar) in self.bars.iter().enumerate() {
+            for (phase, x, y, width, height) in self.segment_rects(row, zoom) {
+                svg.push_str(&format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                    x,
+                    y,
+                    width,
+                    height,
+                    phase.color().0
+                ));
+            }
+            let (x, y) = self.label_position(row, zoom);
+            svg.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\"><title>{}</title>{}</text>\n",
+                x,
+                y,
+                escape_xml(&bar.describe()),
+                escape_xml(&bar.label)
+            ));
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
@@ -0,0 +1,82 @@
+extern crate libc;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+use systemd::dbus::DbusHandle;
+
+/// A loaded timer, like a row of `systemctl list-timers`. Times are in microseconds since the
+/// epoch, and are `None` when the timer won't elapse or hasn't been triggered yet.
+#[derive(Clone, Debug)]
+pub struct Timer {
+    pub name: String,
+    pub next: Option<u64>,
+    pub last: Option<u64>,
+    /// The unit started when the timer elapses.
+    pub activates: String,
+}
+
+impl Timer {
+    /// The time left until the timer elapses.
+    pub fn left(&self, now: u64) -> Option<u64> {
+        self.next.map(|next| next.saturating_sub(now))
+    }
+
+    /// The time passed since the timer was last triggered.
+    pub fn passed(&self, now: u64) -> Option<u64> {
+        self.last.map(|last| now.saturating_sub(last))
+    }
+}
+
+/// The current time, in microseconds since the epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_micros() as u64)
+        .unwrap_or(0)
+}
+
+/// The current time of the monotonic clock, which the elapses of timers such as
+/// `OnBootSec=` are counted in, in microseconds.
+fn monotonic_now() -> u64 {
+    let mut time: libc::timespec = unsafe { ::std::mem::zeroed() };
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut time) };
+    time.tv_sec as u64 * 1_000_000 + time.tv_nsec as u64 / 1000
+}
+
+/// Collects every loaded timer, ordered by when they next elapse. Timers which won't elapse
+/// again are listed last. Like `systemctl list-timers`, the next elapse is the earliest of the
+/// calendar and monotonic elapses.
+pub fn list_timers(handle: &DbusHandle) -> Result<Vec<Timer>, String> {
+    let units = handle
+        .list_units()
+        .map_err(|error| format!("Unable to list the units: {:?}", error))?;
+    let realtime = now();
+    let monotonic = monotonic_now();
+
+    let mut timers = Vec::new();
+    for unit in units.into_iter().filter(|x| x.name.ends_with(".timer")) {
+        let properties = handle
+            .get_unit_properties(&unit.name, "org.freedesktop.systemd1.Timer")
+            .map_err(|error| {
+                format!("Unable to get the properties of {}: {:?}", unit.name, error)
+            })?;
+        let timestamp = |name: &str| properties.u64(name).filter(|&x| x != 0 && x != u64::MAX);
+        let calendar = timestamp("NextElapseUSecRealtime");
+        let elapse = timestamp("NextElapseUSecMonotonic")
+            .map(|elapse| (elapse + realtime).saturating_sub(monotonic));
+        let next = match (calendar, elapse) {
+            (Some(calendar), Some(elapse)) => Some(calendar.min(elapse)),
+            (calendar, elapse) => calendar.or(elapse),
+        };
+        timers.push(Timer {
+            name: unit.name,
+            next,
+            last: timestamp("LastTriggerUSec"),
+            activates: properties.string("Unit").unwrap_or_default(),
+        });
+    }
+    timers.sort_by(|a, b| {
+        let next = |timer: &Timer| timer.next.unwrap_or(u64::MAX);
+        next(a).cmp(&next(b)).then_with(|| a.name.cmp(&b.name))
+    });
+    Ok(timers)
+}
@@ -0,0 +1,297 @@
+const USEC_PER_MSEC: u64 = 1000;
+const USEC_PER_SEC: u64 = 1000 * USEC_PER_MSEC;
+const USEC_PER_MINUTE: u64 = 60 * USEC_PER_SEC;
+const USEC_PER_HOUR: u64 = 60 * USEC_PER_MINUTE;
+const USEC_PER_DAY: u64 = 24 * USEC_PER_HOUR;
+const USEC_PER_WEEK: u64 = 7 * USEC_PER_DAY;
+/// systemd counts a month as a twelfth of a year, and a year as 365.25 days.
+const USEC_PER_MONTH: u64 = 2_629_800 * USEC_PER_SEC;
+const USEC_PER_YEAR: u64 = 31_557_600 * USEC_PER_SEC;
+
+/// Every unit accepted by systemd, along with the number of microseconds in it. Nanoseconds
+/// are accepted as well, but are handled separately as they are smaller than a microsecond.
+const UNITS: [(&str, u64); 30] = [
+    ("seconds", USEC_PER_SEC),
+    ("second", USEC_PER_SEC),
+    ("sec", USEC_PER_SEC),
+    ("s", USEC_PER_SEC),
+    ("minutes", USEC_PER_MINUTE),
+    ("minute", USEC_PER_MINUTE),
+    ("min", USEC_PER_MINUTE),
+    ("months", USEC_PER_MONTH),
+    ("month", USEC_PER_MONTH),
+    ("M", USEC_PER_MONTH),
+    ("msec", USEC_PER_MSEC),
+    ("ms", USEC_PER_MSEC),
+    ("m", USEC_PER_MINUTE),
+    ("hours", USEC_PER_HOUR),
+    ("hour", USEC_PER_HOUR),
+    ("hr", USEC_PER_HOUR),
+    ("h", USEC_PER_HOUR),
+    ("days", USEC_PER_DAY),
+    ("day", USEC_PER_DAY),
+    ("d", USEC_PER_DAY),
+    ("weeks", USEC_PER_WEEK),
+    ("week", USEC_PER_WEEK),
+    ("w", USEC_PER_WEEK),
+    ("years", USEC_PER_YEAR),
+    ("year", USEC_PER_YEAR),
+    ("y", USEC_PER_YEAR),
+    ("usec", 1),
+    ("us", 1),
+    ("\u{3bc}s", 1),
+    ("\u{b5}s", 1),
+];
+
+/// The units used when formatting a time span, from the largest to the smallest.
+const FORMAT_UNITS: [(&str, u64); 9] = [
+    ("y", USEC_PER_YEAR),
+    ("month", USEC_PER_MONTH),
+    ("w", USEC_PER_WEEK),
+    ("d", USEC_PER_DAY),
+    ("h", USEC_PER_HOUR),
+    ("min", USEC_PER_MINUTE),
+    ("s", USEC_PER_SEC),
+    ("ms", USEC_PER_MSEC),
+    ("us", 1),
+];
+
+/// Parses a time span such as `1min 2.345s`, `5h30m` or `infinity` into microseconds.
+/// Components may be separated by whitespace or written next to each other, and a number
+/// without a unit counts as seconds. `infinity` is returned as `u64::MAX`.
+pub fn parse(input: &str) -> Result<u64, String> {
+    let invalid = || format!("Invalid time span: {:?}", input);
+    let trimmed = input.trim();
+    if trimmed == "infinity" {
+        return Ok(u64::MAX);
+    }
+    if trimmed.is_empty() {
+        return Err(invalid());
+    }
+
+    let mut total: u64 = 0;
+    let mut rest = trimmed;
+    while !rest.is_empty() {
+        let number_length = rest
+            .find(|x: char| !x.is_ascii_digit() && x != '.')
+            .unwrap_or(rest.len());
+        let (number, after) = rest.split_at(number_length);
+        let after = after.trim_start();
+        let unit_length = after
+            .find(|x: char| x.is_ascii_digit() || x == '.' || x.is_whitespace())
+            .unwrap_or(after.len());
+        let (unit, after) = after.split_at(unit_length);
+        rest = after.trim_start();
+
+        let (whole, fraction) = match number.find('.') {
+            Some(dot) => (&number[..dot], &number[dot + 1..]),
+            None => (number, ""),
+        };
+        if whole.is_empty() && fraction.is_empty() {
+            return Err(invalid());
+        }
+        if !fraction.chars().all(|x| x.is_ascii_digit()) {
+            return Err(invalid());
+        }
+
+        let usec = match unit {
+            "" => USEC_PER_SEC,
+            "ns" | "nsec" => {
+                let nsec = parse_component(whole, fraction, 1).ok_or_else(invalid)?;
+                total = total.checked_add(nsec / 1000).ok_or_else(invalid)?;
+                continue;
+            }
+            _ => UNITS
+                .iter()
+                .find(|&&(name, _)| name == unit)
+                .map(|&(_, usec)| usec)
+                .ok_or_else(invalid)?,
+        };
+        let value = parse_component(whole, fraction, usec).ok_or_else(invalid)?;
+        total = total.checked_add(value).ok_or_else(invalid)?;
+    }
+    Ok(total)
+}
+
+/// Multiplies a number written as its whole and fractional digits by a unit, discarding the
+/// digits which are too small to be represented.
+fn parse_component(whole: &str, fraction: &str, unit: u64) -> Option<u64> {
+    let whole = if whole.is_empty() {
+        0
+    } else {
+        whole.parse::<u64>().ok()?
+    };
+    let mut value = whole.checked_mul(unit)?;
+    let mut scale = unit;
+    for digit in fraction.chars() {
+        scale /= 10;
+        if scale == 0 {
+            break;
+        }
+        value = value.checked_add(u64::from(digit.to_digit(10)?) * scale)?;
+    }
+    Some(value)
+}
+
+/// Formats a time span in microseconds the way `systemd-analyze` does, such as `1min 2.345s`,
+/// with a millisecond accuracy.
+pub fn format(usec: u64) -> String {
+    format_with_accuracy(usec, USEC_PER_MSEC)
+}
+
+/// Formats a time span in microseconds, leaving out the components smaller than the accuracy.
+/// The largest component is always shown, so that small spans don't read as zero. Spans shorter
+/// than a minute end with a fractional component, such as `2.5s`.
+pub fn format_with_accuracy(usec: u64, accuracy: u64) -> String {
+    if usec == u64::MAX {
+        return String::from("infinity");
+    }
+    if usec == 0 {
+        return String::from("0");
+    }
+
+    let accuracy = accuracy.max(1);
+    let mut components: Vec<String> = Vec::new();
+    let mut rest = usec;
+    for &(suffix, unit) in FORMAT_UNITS.iter() {
+        if rest == 0 || (rest < accuracy && !components.is_empty()) {
+            break;
+        }
+        if rest < unit {
+            continue;
+        }
+
+        let whole = rest / unit;
+        let remainder = rest % unit;
+        if rest < USEC_PER_MINUTE && remainder > 0 && unit > accuracy {
+            // Show as many decimals as the accuracy allows, without the trailing zeros.
+            let mut digits = 0;
+            let mut scale = unit;
+            while scale > accuracy && scale > 1 {
+                scale /= 10;
+                digits += 1;
+            }
+            let fraction = format!("{:0width$}", remainder / scale, width = digits);
+            let fraction = fraction.trim_end_matches('0');
+            if fraction.is_empty() {
+                components.push(format!("{}{}", whole, suffix));
+            } else {
+                components.push(format!("{}.{}{}", whole, fraction, suffix));
+            }
+            break;
+        }
+
+        components.push(format!("{}{}", whole, suffix));
+        rest = remainder;
+    }
+    components.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_single_components() {
+        assert_eq!(parse("5s"), Ok(5 * USEC_PER_SEC));
+        assert_eq!(parse("123ms"), Ok(123 * USEC_PER_MSEC));
+        assert_eq!(parse("250us"), Ok(250));
+        assert_eq!(parse("250\u{b5}s"), Ok(250));
+        assert_eq!(parse("3min"), Ok(3 * USEC_PER_MINUTE));
+        assert_eq!(parse("3m"), Ok(3 * USEC_PER_MINUTE));
+        assert_eq!(parse("2h"), Ok(2 * USEC_PER_HOUR));
+        assert_eq!(parse("1d"), Ok(USEC_PER_DAY));
+        assert_eq!(parse("1w"), Ok(USEC_PER_WEEK));
+        assert_eq!(parse("1M"), Ok(USEC_PER_MONTH));
+        assert_eq!(parse("1y"), Ok(USEC_PER_YEAR));
+        assert_eq!(parse("1500ns"), Ok(1));
+    }
+
+    #[test]
+    fn parses_long_unit_names() {
+        assert_eq!(parse("2 seconds"), Ok(2 * USEC_PER_SEC));
+        assert_eq!(parse("1 minute"), Ok(USEC_PER_MINUTE));
+        assert_eq!(parse("4 hours"), Ok(4 * USEC_PER_HOUR));
+        assert_eq!(parse("2 weeks"), Ok(2 * USEC_PER_WEEK));
+        assert_eq!(parse("6 months"), Ok(6 * USEC_PER_MONTH));
+        assert_eq!(parse("10 msec"), Ok(10 * USEC_PER_MSEC));
+    }
+
+    #[test]
+    fn parses_multiple_components() {
+        assert_eq!(
+            parse("1min 2.345s"),
+            Ok(USEC_PER_MINUTE + 2 * USEC_PER_SEC + 345 * USEC_PER_MSEC)
+        );
+        assert_eq!(parse("5h30m"), Ok(5 * USEC_PER_HOUR + 30 * USEC_PER_MINUTE));
+        assert_eq!(
+            parse("1h 2min 3s 4ms 5us"),
+            Ok(USEC_PER_HOUR + 2 * USEC_PER_MINUTE + 3 * USEC_PER_SEC + 4 * USEC_PER_MSEC + 5)
+        );
+    }
+
+    #[test]
+    fn parses_fractions_and_bare_numbers() {
+        assert_eq!(parse("1.5h"), Ok(USEC_PER_HOUR + 30 * USEC_PER_MINUTE));
+        assert_eq!(parse(".5s"), Ok(500 * USEC_PER_MSEC));
+        assert_eq!(parse("0.0000001s"), Ok(0));
+        assert_eq!(parse("30"), Ok(30 * USEC_PER_SEC));
+        assert_eq!(parse("  7s  "), Ok(7 * USEC_PER_SEC));
+        assert_eq!(parse("infinity"), Ok(u64::MAX));
+    }
+
+    #[test]
+    fn rejects_invalid_spans() {
+        assert!(parse("").is_err());
+        assert!(parse("s").is_err());
+        assert!(parse("5 parsecs").is_err());
+        assert!(parse("1.2.3s").is_err());
+        assert!(parse("-5s").is_err());
+        assert!(parse("99999999999999999999y").is_err());
+    }
+
+    #[test]
+    fn formats_like_systemd_analyze() {
+        assert_eq!(format(0), "0");
+        assert_eq!(format(u64::MAX), "infinity");
+        assert_eq!(format(500), "500us");
+        assert_eq!(format(123 * USEC_PER_MSEC), "123ms");
+        assert_eq!(format(2 * USEC_PER_SEC + 345 * USEC_PER_MSEC), "2.345s");
+        assert_eq!(format(2 * USEC_PER_SEC + 300 * USEC_PER_MSEC), "2.3s");
+        assert_eq!(format(5 * USEC_PER_SEC), "5s");
+        assert_eq!(
+            format(USEC_PER_MINUTE + 2 * USEC_PER_SEC + 345 * USEC_PER_MSEC),
+            "1min 2.345s"
+        );
+        assert_eq!(
+            format(USEC_PER_HOUR + 2 * USEC_PER_MINUTE + 3 * USEC_PER_SEC),
+            "1h 2min 3s"
+        );
+        assert_eq!(format(USEC_PER_DAY + 5), "1d");
+    }
+
+    #[test]
+    fn formats_with_other_accuracies() {
+        let usec = 2 * USEC_PER_SEC + 345_678;
+        assert_eq!(format_with_accuracy(usec, 1), "2.345678s");
+        assert_eq!(format_with_accuracy(usec, USEC_PER_SEC), "2s");
+        assert_eq!(format_with_accuracy(1_234, 1), "1.234ms");
+    }
+
+    #[test]
+    fn formatted_spans_parse_back() {
+        for &usec in [
+            1,
+            999,
+            1_000,
+            1_500_000,
+            61_000_000,
+            3_723_000_000,
+            USEC_PER_WEEK,
+        ]
+        .iter()
+        {
+            assert_eq!(parse(&format_with_accuracy(usec, 1)), Ok(usec));
+        }
+    }
+}
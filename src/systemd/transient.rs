@@ -0,0 +1,284 @@
+use std::io;
+use std::process::{Child, Command};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Whether the command is started by systemd as a service, or by us within a scope.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransientKind {
+    Service,
+    Scope,
+}
+
+/// When a transient service should be started by a transient timer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Trigger {
+    /// A calendar event expression, as given to `OnCalendar=`.
+    Calendar(String),
+    /// A delay after the timer is started, in microseconds, as given to `OnActiveSec=`.
+    Delay(u64),
+}
+
+/// A command to run as a transient unit, like `systemd-run` does.
+#[derive(Clone, Debug)]
+pub struct TransientUnit {
+    /// The name of the unit, without its type suffix.
+    pub name: String,
+    pub kind: TransientKind,
+    pub command: Vec<String>,
+    /// Environment variables, written as `NAME=value`.
+    pub environment: Vec<String>,
+    pub working_directory: Option<String>,
+    pub user: Option<String>,
+    /// Resource control properties, such as `MemoryMax`.
+    pub limits: Vec<(&'static str, u64)>,
+    pub trigger: Option<Trigger>,
+    /// Keeps a service around once its command exits, so that it can still be inspected.
+    pub remain_after_exit: bool,
+}
+
+impl TransientUnit {
+    /// Names the unit like `systemd-run` does when no name was given.
+    pub fn generate_name() -> String {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|x| x.as_micros())
+            .unwrap_or(0);
+        format!("run-u{}", now)
+    }
+
+    /// The name of the unit running the command, including its type suffix.
+    pub fn unit_name(&self) -> String {
+        match self.kind {
+            TransientKind::Service => format!("{}.service", self.name),
+            TransientKind::Scope => format!("{}.scope", self.name),
+        }
+    }
+
+    /// The name of the timer which triggers the service, if one was requested.
+    pub fn timer_name(&self) -> Option<String> {
+        self.trigger
+            .as_ref()
+            .map(|_| format!("{}.timer", self.name))
+    }
+
+    /// Checks that the unit can be started as described.
+    pub fn validate(&self) -> Result<(), String> {
+        let valid_name = |x: char| x.is_ascii_alphanumeric() || ":-_.\\@".contains(x);
+        if self.name.is_empty() || !self.name.chars().all(valid_name) {
+            return Err(format!("Invalid unit name: {:?}", self.name));
+        }
+        if self.command.is_empty() {
+            return Err(String::from("No command was given"));
+        }
+        if !self.command[0].starts_with('/') && self.kind == TransientKind::Service {
+            return Err(format!(
+                "The command must be an absolute path: {:?}",
+                self.command[0]
+            ));
+        }
+        if self.kind == TransientKind::Scope {
+            if self.user.is_some() {
+                return Err(String::from("A user can only be set for services"));
+            }
+            if self.trigger.is_some() {
+                return Err(String::from("Only services can be started by a timer"));
+            }
+            if self.remain_after_exit {
+                return Err(String::from(
+                    "Only services can remain after their command exits",
+                ));
+            }
+        }
+        if let Some(variable) = self.environment.iter().find(|x| !x.contains('=')) {
+            return Err(format!("Invalid environment variable: {:?}", variable));
+        }
+        Ok(())
+    }
+
+    /// Starts the command of a scope as our own child, so that it can be moved into the scope.
+    /// The child must be reaped by the caller once it exits.
+    pub fn spawn(&self) -> io::Result<Child> {
+        let mut command = Command::new(&self.command[0]);
+        command.args(&self.command[1..]);
+        for variable in &self.environment {
+            let mut parts = variable.splitn(2, '=');
+            if let (Some(name), Some(value)) = (parts.next(), parts.next()) {
+                command.env(name, value);
+            }
+        }
+        if let Some(ref directory) = self.working_directory {
+            command.current_dir(directory);
+        }
+        command.spawn()
+    }
+}
+
+/// Splits a command line into its arguments. Arguments are separated by whitespace, unless it
+/// is quoted with single or double quotes or escaped with a backslash.
+pub fn split_command_line(input: &str) -> Result<Vec<String>, String> {
+    let mut arguments = Vec::new();
+    let mut current = String::new();
+    let mut in_argument = false;
+    let mut quote = None;
+    let mut chars = input.chars();
+
+    while let Some(character) = chars.next() {
+        match (quote, character) {
+            (Some(open), _) if character == open => quote = None,
+            (Some('"'), '\\') | (None, '\\') => match chars.next() {
+                Some(escaped) => current.push(escaped),
+                None => return Err(String::from("The command line ends with a backslash")),
+            },
+            (Some(_), _) => current.push(character),
+            (None, '"') | (None, '\'') => {
+                quote = Some(character);
+                in_argument = true;
+                continue;
+            }
+            (None, _) if character.is_whitespace() => {
+                if in_argument {
+                    arguments.push(current.clone());
+                    current.clear();
+                    in_argument = false;
+                }
+                continue;
+            }
+            (None, _) => current.push(character),
+        }
+        in_argument = true;
+    }
+
+    if quote.is_some() {
+        return Err(String::from("The command line has an unterminated quote"));
+    }
+    if in_argument {
+        arguments.push(current);
+    }
+    Ok(arguments)
+}
+
+/// Finds the absolute path of a program the way a shell would, by searching the directories
+/// listed in `PATH`. Paths containing a slash are returned as they are.
+pub fn find_executable(program: &str) -> Option<String> {
+    if program.contains('/') {
+        return Some(program.to_owned());
+    }
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|directory| directory.join(program))
+        .find(|candidate| candidate.is_file())
+        .map(|candidate| candidate.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit(kind: TransientKind, command: &[&str]) -> TransientUnit {
+        TransientUnit {
+            name: String::from("run-test"),
+            kind,
+            command: command.iter().map(|x| x.to_string()).collect(),
+            environment: Vec::new(),
+            working_directory: None,
+            user: None,
+            limits: Vec::new(),
+            trigger: None,
+            remain_after_exit: false,
+        }
+    }
+
+    #[test]
+    fn splits_on_whitespace() {
+        assert_eq!(
+            split_command_line("  /bin/echo  one\ttwo \n"),
+            Ok(vec![
+                String::from("/bin/echo"),
+                String::from("one"),
+                String::from("two")
+            ])
+        );
+        assert_eq!(split_command_line(""), Ok(Vec::new()));
+        assert_eq!(split_command_line("   "), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn keeps_quoted_arguments_together() {
+        assert_eq!(
+            split_command_line(r#"sh -c "echo 'a  b'" 'x "y"'"#),
+            Ok(vec![
+                String::from("sh"),
+                String::from("-c"),
+                String::from("echo 'a  b'"),
+                String::from(r#"x "y""#)
+            ])
+        );
+        assert_eq!(
+            split_command_line(r#"a"b"'c' "" ''"#),
+            Ok(vec![String::from("abc"), String::new(), String::new()])
+        );
+    }
+
+    #[test]
+    fn unescapes_backslashes() {
+        assert_eq!(
+            split_command_line(r#"one\ two "a\"b" 'c\d' \\"#),
+            Ok(vec![
+                String::from("one two"),
+                String::from("a\"b"),
+                String::from("c\\d"),
+                String::from("\\")
+            ])
+        );
+    }
+
+    #[test]
+    fn rejects_unterminated_quotes_and_escapes() {
+        assert!(split_command_line("echo \"unterminated").is_err());
+        assert!(split_command_line("echo 'unterminated").is_err());
+        assert!(split_command_line("echo \\").is_err());
+        assert!(split_command_line("echo \"\\").is_err());
+    }
+
+    #[test]
+    fn finds_executables_on_the_path() {
+        assert_eq!(
+            find_executable("./relative/program"),
+            Some(String::from("./relative/program"))
+        );
+        assert_eq!(find_executable("no-such-program-for-systemd-manager"), None);
+        if let Some(path) = find_executable("sh") {
+            assert!(path.starts_with('/') && path.ends_with("/sh"));
+        }
+    }
+
+    #[test]
+    fn validates_units() {
+        assert!(unit(TransientKind::Service, &["/bin/true"])
+            .validate()
+            .is_ok());
+        assert!(unit(TransientKind::Scope, &["true"]).validate().is_ok());
+        assert!(unit(TransientKind::Service, &[]).validate().is_err());
+        assert!(unit(TransientKind::Service, &["true"]).validate().is_err());
+
+        let mut invalid = unit(TransientKind::Service, &["/bin/true"]);
+        invalid.name = String::from("run test");
+        assert!(invalid.validate().is_err());
+        invalid.name.clear();
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = unit(TransientKind::Service, &["/bin/true"]);
+        invalid.environment.push(String::from("NO_VALUE"));
+        assert!(invalid.validate().is_err());
+
+        let mut scope = unit(TransientKind::Scope, &["true"]);
+        scope.trigger = Some(Trigger::Delay(1_000_000));
+        assert!(scope.validate().is_err());
+        let mut scope = unit(TransientKind::Scope, &["true"]);
+        scope.user = Some(String::from("nobody"));
+        assert!(scope.validate().is_err());
+        let mut scope = unit(TransientKind::Scope, &["true"]);
+        scope.remain_after_exit = true;
+        assert!(scope.validate().is_err());
+    }
+}
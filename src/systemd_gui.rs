@@ -1,13 +1,28 @@
 use gdk::keys::constants;
 use gtk;
 use gtk::prelude::*;
-use systemd::analyze::Analyze;
-use systemd::dbus::{self, UnitState};
+use systemd::analyze::{self, Analyze, BootTimes};
+use systemd::calendar;
+use systemd::dbus::{self, BatchAction, DbusHandle, SystemdUnit, UnitState};
+use systemd::dependencies;
+use systemd::graph::{self, Graph};
+use systemd::history::{self, BootHistory, BootRecord};
+use systemd::journal::{self, Entry, Filter, Invocation, OutputFormat, Pager, Search};
+use systemd::resources::{self, History, Sample};
+use systemd::security::{self, Assessment};
+use systemd::sockets;
+use systemd::status;
+use systemd::timeline::Timeline;
+use systemd::timers;
+use systemd::timespan;
+use systemd::transient::{self, TransientKind, TransientUnit, Trigger};
 
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::fs;
-use std::io::Write;
+use std::io::{self, Write};
 use std::path::Path;
-use std::process::Command;
+use std::rc::Rc;
 
 use crate::Config;
 
@@ -42,13 +57,45 @@ fn create_row(
     state_icons.push(image);
 }
 
+/// Adds a resizable text column to the tree for each `(title, text, sort)`, which shows the
+/// `text` column of the model and sorts the rows by the `sort` column, if any. When a `color`
+/// column is given, it holds the foreground color of the rows, and rows without a color keep the
+/// default one of the theme. Returns the columns along with their renderers.
+fn add_text_columns(
+    tree: &gtk::TreeView,
+    columns: &[(&str, i32, Option<i32>)],
+    color: Option<i32>,
+) -> Vec<(gtk::TreeViewColumn, gtk::CellRendererText)> {
+    columns
+        .iter()
+        .map(|&(title, text, sort)| {
+            let column = gtk::TreeViewColumn::new();
+            let renderer = gtk::CellRendererText::new();
+            column.set_title(title);
+            column.set_resizable(true);
+            if let Some(sort) = sort {
+                column.set_sort_column_id(sort);
+            }
+            column.pack_start(&renderer, true);
+            column.add_attribute(&renderer, "text", text);
+            if let Some(color) = color {
+                column.add_attribute(&renderer, "foreground", color);
+            }
+            tree.append_column(&column);
+            (column, renderer)
+        })
+        .collect()
+}
+
 /// Read the unit file and return it's contents so that we can display it in the `gtk::TextView`.
 fn get_unit_info<P: AsRef<Path>>(path: P) -> String {
     fs::read_to_string(path).unwrap()
 }
 
-/// Use `systemd-analyze blame` to fill out the information for the Analyze `gtk::Stack`.
-fn setup_systemd_analyze(builder: &gtk::Builder) {
+/// Use the boot timestamps of the manager and `systemd-analyze blame` to fill out the
+/// information for the Analyze `gtk::Stack`. Once the boot has finished, its timing is saved to
+/// the boot history.
+fn setup_systemd_analyze(builder: &gtk::Builder, handle: &DbusHandle, history: &BootHistory) {
     let analyze_tree: gtk::TreeView = builder.get_object("analyze_tree").unwrap();
     let analyze_store = gtk::ListStore::new(&[glib::types::Type::U32, glib::types::Type::String]);
 
@@ -68,118 +115,2844 @@ fn setup_systemd_analyze(builder: &gtk::Builder) {
     add_column!(analyze_store, "Time (ms)", 0);
     add_column!(analyze_store, "Unit", 1);
 
-    let units = Analyze::blame();
-    for value in units.clone() {
+    let blame = Analyze::blame().unwrap_or_else(|error| {
+        println!("{}", error);
+        Vec::new()
+    });
+    for value in &blame {
         analyze_store.insert_with_values(None, &[0, 1], &[&value.time, &value.service]);
     }
 
     analyze_tree.set_model(Some(&analyze_store));
 
     let total_time_label: gtk::Label = builder.get_object("time_to_boot").unwrap();
-    let time = (units.iter().last().unwrap().time as f32) / 1000f32;
-    total_time_label.set_label(format!("{} seconds", time).as_str());
+    let phases_grid: gtk::Grid = builder.get_object("boot_phases_grid").unwrap();
+    let times = match handle.get_manager_properties() {
+        Ok(properties) => BootTimes::from_properties(&properties),
+        Err(error) => {
+            println!("Unable to get the properties of the manager: {:?}", error);
+            return;
+        }
+    };
+    let times = match times {
+        Some(times) => times,
+        None => {
+            total_time_label.set_label("Bootup is not yet finished");
+            return;
+        }
+    };
+
+    total_time_label.set_label(&timespan::format(times.total()));
+    total_time_label.set_tooltip_text(Some(&times.summary()));
+    let mut rows = times
+        .phases()
+        .into_iter()
+        .map(|(phase, duration)| (phase, timespan::format(duration)))
+        .collect::<Vec<_>>();
+    if let Some(started) = times.kernel_started {
+        rows.push(("kernel started", status::format_timestamp(started)));
+    }
+    for (row, (phase, value)) in rows.into_iter().enumerate() {
+        let name = gtk::Label::new(Some(phase));
+        name.set_xalign(1f32);
+        let value = gtk::Label::new(Some(value.as_str()));
+        value.set_xalign(0f32);
+        value.set_selectable(true);
+        phases_grid.attach(&name, 0, row as i32, 1, 1);
+        phases_grid.attach(&value, 1, row as i32, 1, 1);
+    }
+    phases_grid.show_all();
+
+    if blame.is_empty() {
+        return;
+    }
+    let saved = history::current_boot_id()
+        .and_then(|id| history.record(&BootRecord::new(id, &times, &blame)));
+    if let Err(error) = saved {
+        println!("Unable to record the boot in the history: {}", error);
+    }
+}
+
+/// Draws the trend of the total and userspace boot times across the recorded boots.
+fn draw_boot_history(cr: &cairo::Context, width: f64, height: f64, records: &[BootRecord]) {
+    cr.set_source_rgb(1.0, 1.0, 1.0);
+    cr.paint();
+    cr.set_line_width(1.0);
+    cr.set_font_size(12.0);
+
+    let total: Vec<f64> = records.iter().map(|x| x.total() as f64).collect();
+    let userspace: Vec<f64> = records
+        .iter()
+        .map(|x| x.phase("userspace").unwrap_or(0) as f64)
+        .collect();
+    let latest = |values: &[f64]| {
+        values.last().map_or_else(
+            || String::from("n/a"),
+            |&x| format!("{} ({} boots)", timespan::format(x as u64), values.len()),
+        )
+    };
+
+    let charts: [(&str, &[f64]); 2] = [("Total", &total), ("Userspace", &userspace)];
+    let chart_height = (height - 10.0) / charts.len() as f64;
+    for (index, (title, values)) in charts.iter().enumerate() {
+        let area = (
+            5.0,
+            5.0 + index as f64 * chart_height,
+            width - 10.0,
+            chart_height - 5.0,
+        );
+        draw_chart(cr, area, title, values, records.len(), &latest(values));
+    }
+}
+
+/// Adds the columns of the comparison between two boots and returns its model. The fifth column
+/// of the model holds the color of the row, which highlights the units that got slower.
+fn setup_boot_delta_tree(tree: &gtk::TreeView) -> gtk::ListStore {
+    let store = gtk::ListStore::new(&[
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+    ]);
+
+    let columns = [
+        ("Unit", 0, None),
+        ("Before", 1, None),
+        ("After", 2, None),
+        ("Change", 3, None),
+    ];
+    add_text_columns(tree, &columns, Some(4));
+
+    tree.set_model(Some(&store));
+    store
+}
+
+/// Lists how the activation time of each unit changed between two recorded boots.
+fn update_boot_deltas(store: &gtk::ListStore, before: &BootRecord, after: &BootRecord) {
+    store.clear();
+    let describe = |time: Option<u64>| time.map_or_else(|| String::from("-"), timespan::format);
+    for delta in history::compare(before, after) {
+        let change = delta.delta();
+        let sign = if change < 0 { "-" } else { "+" };
+        let change_text = format!("{}{}", sign, timespan::format(change.unsigned_abs()));
+        let color = if change > 0 { Some("red") } else { None };
+        store.insert_with_values(
+            None,
+            &[0, 1, 2, 3, 4],
+            &[
+                &delta.unit,
+                &describe(delta.before),
+                &describe(delta.after),
+                &change_text,
+                &color,
+            ],
+        );
+    }
+}
+
+/// Adds the Unit and Time columns to the critical chain tree and returns its model. The third
+/// column of the model holds the color of the unit, which highlights the units that took time
+/// to activate.
+fn setup_critical_chain_tree(tree: &gtk::TreeView) -> gtk::TreeStore {
+    let store = gtk::TreeStore::new(&[
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+    ]);
+
+    add_text_columns(tree, &[("Unit", 0, None), ("Time", 1, None)], Some(2));
+
+    tree.set_model(Some(&store));
+    store
+}
+
+/// Rebuilds the critical chain tree of the unit, nesting each link under the link it delayed.
+fn update_critical_chain(
+    tree: &gtk::TreeView,
+    store: &gtk::TreeStore,
+    handle: &DbusHandle,
+    unit: &str,
+) {
+    store.clear();
+    let links = match analyze::critical_chain(handle, unit) {
+        Ok(links) => links,
+        Err(error) => {
+            println!("{}", error);
+            return;
+        }
+    };
+
+    let mut parents: Vec<gtk::TreeIter> = Vec::new();
+    for link in links {
+        parents.truncate(link.depth);
+        let color = link.duration.map(|_| "red");
+        let iter = store.insert_with_values(
+            parents.last(),
+            None,
+            &[0, 1, 2],
+            &[&link.unit, &link.annotation(), &color],
+        );
+        parents.push(iter);
+    }
+    tree.expand_all();
+}
+
+/// Adds the columns of the ranked list of services and returns its model. The exposure is stored
+/// both as a number to sort the rows by and as the text which is displayed, and the fifth column
+/// holds the color of the row, which highlights the exposed services.
+fn setup_security_tree(tree: &gtk::TreeView) -> gtk::ListStore {
+    let store = gtk::ListStore::new(&[
+        glib::types::Type::String,
+        glib::types::Type::F64,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+    ]);
+
+    let columns = [
+        ("Unit", 0, Some(0)),
+        ("Exposure", 2, Some(1)),
+        ("Level", 3, Some(1)),
+    ];
+    add_text_columns(tree, &columns, Some(4));
+
+    store.set_sort_column_id(gtk::SortColumn::Index(1), gtk::SortType::Descending);
+    tree.set_model(Some(&store));
+    store
+}
+
+/// Lists the exposure of every assessed service.
+fn update_security(store: &gtk::ListStore, assessments: &[Assessment]) {
+    store.clear();
+    for assessment in assessments {
+        let color = if assessment.exposure > 7.0 {
+            Some("red")
+        } else {
+            None
+        };
+        store.insert_with_values(
+            None,
+            &[0, 1, 2, 3, 4],
+            &[
+                &assessment.unit,
+                &assessment.exposure,
+                &format!("{:.1}", assessment.exposure),
+                &assessment.level(),
+                &color,
+            ],
+        );
+    }
+}
+
+/// Adds the columns of the breakdown of a service and returns its model. The first column of
+/// the model tells whether the suggestion of the row goes into the override, and the last one
+/// whether the row has a suggestion to choose at all.
+fn setup_security_findings_tree(tree: &gtk::TreeView) -> gtk::ListStore {
+    let store = gtk::ListStore::new(&[
+        glib::types::Type::Bool,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::Bool,
+    ]);
+
+    let column = gtk::TreeViewColumn::new();
+    let renderer = gtk::CellRendererToggle::new();
+    column.set_title("Override");
+    column.pack_start(&renderer, false);
+    column.add_attribute(&renderer, "active", 0);
+    column.add_attribute(&renderer, "activatable", 6);
+    column.add_attribute(&renderer, "visible", 6);
+    tree.append_column(&column);
+    {
+        let store = store.clone();
+        renderer.connect_toggled(move |_, path| {
+            if let Some(iter) = store.get_iter(&path) {
+                let chosen = store.get_value(&iter, 0).get_some::<bool>().unwrap();
+                store.set_value(&iter, 0, &(!chosen).to_value());
+            }
+        });
+    }
+
+    let columns = [
+        ("Setting", 1, None),
+        ("Description", 2, None),
+        ("Exposure", 3, None),
+    ];
+    add_text_columns(tree, &columns, Some(5));
+
+    tree.set_model(Some(&store));
+    store
+}
+
+/// Lists every check of the service, from the setting exposing it the most. The suggestions of
+/// the failed checks are chosen for the override by default.
+fn update_security_findings(store: &gtk::ListStore, assessment: &Assessment) {
+    store.clear();
+    let mut findings: Vec<&security::Finding> = assessment.findings.iter().collect();
+    findings.sort_by(|a, b| {
+        b.exposure
+            .partial_cmp(&a.exposure)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    for finding in findings {
+        let passed = finding.passed();
+        let color = if passed { None } else { Some("red") };
+        store.insert_with_values(
+            None,
+            &[0, 1, 2, 3, 4, 5, 6],
+            &[
+                &!passed,
+                &finding.setting,
+                &finding.description,
+                &format!("{:.1}", finding.exposure),
+                &finding.suggestion,
+                &color,
+                &!passed,
+            ],
+        );
+    }
 }
 
-/// Updates the associated journal `TextView` with the contents of the unit's journal log.
-fn update_journal(journal: &gtk::TextView, unit_path: &str, user: bool) {
-    journal
+/// Shows the drop-in made of the chosen suggestions so that it can be reviewed and edited, then
+/// writes it and reloads the manager. Returns whether the drop-in was written.
+fn create_security_override(
+    window: &gtk::Window,
+    handle: &DbusHandle,
+    unit: &str,
+    suggestions: &[String],
+    user: bool,
+) -> bool {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(format!("Sandboxing Override of {}", unit).as_str()),
+        Some(window),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            ("Cancel", gtk::ResponseType::Cancel),
+            ("Save", gtk::ResponseType::Accept),
+        ],
+    );
+    dialog.set_default_size(600, 400);
+
+    let path = security::drop_in_path(unit, user);
+    let path_label = gtk::Label::new(Some(path.to_string_lossy().as_ref()));
+    path_label.set_xalign(0f32);
+    let warning = gtk::Label::new(Some(
+        "Some settings may keep the service from working. \
+         The service must be restarted for them to take effect.",
+    ));
+    warning.set_xalign(0f32);
+    warning.set_line_wrap(true);
+    let contents = gtk::TextView::new();
+    contents.set_monospace(true);
+    let suggestions: Vec<&str> = suggestions.iter().map(String::as_str).collect();
+    contents
         .get_buffer()
         .unwrap()
-        .set_text(get_unit_journal(unit_path, user).as_str());
+        .set_text(&security::drop_in(&suggestions));
+    let scroller = gtk::ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+    scroller.set_shadow_type(gtk::ShadowType::In);
+    scroller.add(&contents);
+    let error_label = gtk::Label::new(None);
+
+    let content = dialog.get_content_area();
+    content.pack_start(&path_label, false, true, 5);
+    content.pack_start(&warning, false, true, 5);
+    content.pack_start(&scroller, true, true, 5);
+    content.pack_start(&error_label, false, true, 5);
+    dialog.show_all();
+
+    let mut saved = false;
+    while dialog.run() == gtk::ResponseType::Accept {
+        let buffer = contents.get_buffer().unwrap();
+        let text = buffer
+            .get_text(&buffer.get_start_iter(), &buffer.get_end_iter(), true)
+            .unwrap();
+        if let Err(error) = security::write_drop_in(unit, user, text.as_str()) {
+            error_label.set_text(&format!("Unable to write {}: {}", path.display(), error));
+            continue;
+        }
+        saved = true;
+        match handle.reload() {
+            Some(error) => error_label.set_text(&error),
+            None => break,
+        }
+    }
+    dialog.close();
+    saved
 }
 
-/// Obtains the journal log for the given unit.
-fn get_unit_journal(unit_path: &str, user: bool) -> String {
-    let mut command = Command::new("journalctl");
-    if user {
-        command.arg("--user");
+/// Fills the status grid with what systemd currently reports about the unit, followed by links
+/// to its last crashes.
+fn update_status(
+    grid: &gtk::Grid,
+    handle: &DbusHandle,
+    unit: &str,
+    user: bool,
+    show_crash: &Rc<dyn Fn(&str)>,
+) {
+    for child in grid.get_children() {
+        grid.remove(&child);
+    }
+
+    let fields = match handle.get_unit_properties(unit, "") {
+        Ok(properties) => status::status_fields(&properties),
+        Err(error) => vec![("Error", format!("{:?}", error))],
+    };
+
+    let rows = fields.len() as i32;
+    for (row, (field, value)) in fields.into_iter().enumerate() {
+        let field_label = gtk::Label::new(None);
+        field_label.set_markup(&format!("<b>{}:</b>", field));
+        field_label.set_xalign(1f32);
+        let value_label = gtk::Label::new(Some(value.as_str()));
+        value_label.set_xalign(0f32);
+        value_label.set_selectable(true);
+        value_label.set_line_wrap(true);
+        grid.attach(&field_label, 0, row as i32, 1, 1);
+        grid.attach(&value_label, 1, row as i32, 1, 1);
+    }
+
+    let crashes = journal::list_crashes(user, Some(unit), 3).unwrap_or_default();
+    for (index, crash) in crashes.into_iter().enumerate() {
+        let row = rows + index as i32;
+        if index == 0 {
+            let field_label = gtk::Label::new(None);
+            field_label.set_markup("<b>Crashes:</b>");
+            field_label.set_xalign(1f32);
+            grid.attach(&field_label, 0, row, 1, 1);
+        }
+        let link = gtk::Button::with_label(&format!(
+            "{} {} (PID {}) at {}",
+            crash.executable,
+            crash.signal,
+            crash.pid,
+            status::format_timestamp(crash.time)
+        ));
+        link.set_relief(gtk::ReliefStyle::None);
+        link.set_halign(gtk::Align::Start);
+        link.set_tooltip_text(Some("Show the crash and its stack trace"));
+        let show_crash = show_crash.clone();
+        link.connect_clicked(move |_| show_crash(&crash.cursor));
+        grid.attach(&link, 1, row, 1, 1);
     }
-    command
-        .arg("-b")
-        .arg("-u")
-        .arg(Path::new(unit_path).file_stem().unwrap().to_str().unwrap());
+    grid.show_all();
+}
+
+/// Adds the Unit, Relation and State columns to the dependency tree and returns its model. The
+/// fourth column of the model records whether the children of a row have been loaded yet.
+fn setup_dependency_tree(tree: &gtk::TreeView) -> gtk::TreeStore {
+    let store = gtk::TreeStore::new(&[
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::Bool,
+    ]);
 
-    let log = String::from_utf8(command.output().unwrap().stdout).unwrap();
-    log.lines()
-        .rev()
-        .map(|x| x.trim())
-        .fold(String::with_capacity(log.len()), |acc, x| acc + "\n" + x)
+    let columns = [("Unit", 0, None), ("Relation", 1, None), ("State", 2, None)];
+    add_text_columns(tree, &columns, None);
+
+    tree.set_model(Some(&store));
+    store
 }
 
-fn get_filename(path: &str) -> &str {
-    let filename = Path::new(path)
-        .file_name()
-        .unwrap_or_else(|| panic!("Couldn't get filename of path {:?}", path));
-    filename
-        .to_str()
-        .unwrap_or_else(|| panic!("Filename {:?} wasn't valid unicode", filename))
+/// Rebuilds the dependency tree with the unit as its root, and expands the first level.
+fn update_dependencies(
+    tree: &gtk::TreeView,
+    store: &gtk::TreeStore,
+    handle: &DbusHandle,
+    unit: &str,
+    reverse: bool,
+    ordering: bool,
+) {
+    store.clear();
+    let root = store.insert_with_values(None, None, &[0, 1, 2, 3], &[&unit, &"", &"", &false]);
+    append_dependencies(store, handle, &root, reverse, ordering);
+    tree.expand_row(&store.get_path(&root).unwrap(), false);
+}
+
+/// Loads the dependencies of the unit in the given row and appends them as its children. Each
+/// child receives an empty placeholder row so that it can be expanded, unless the unit already
+/// appears above it in the tree.
+fn append_dependencies(
+    store: &gtk::TreeStore,
+    handle: &DbusHandle,
+    parent: &gtk::TreeIter,
+    reverse: bool,
+    ordering: bool,
+) {
+    let unit_of = |iter: &gtk::TreeIter| -> String {
+        store
+            .get_value(iter, 0)
+            .get::<String>()
+            .unwrap()
+            .unwrap_or_default()
+    };
+
+    let unit = unit_of(parent);
+    let mut ancestors = vec![unit.clone()];
+    let mut iter = parent.clone();
+    while let Some(parent) = store.iter_parent(&iter) {
+        ancestors.push(unit_of(&parent));
+        iter = parent;
+    }
+
+    let states: HashMap<String, String> = handle
+        .list_units()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|x| (x.name, format!("{} ({})", x.active_state, x.sub_state)))
+        .collect();
+
+    let dependencies = match handle.get_unit_properties(&unit, "org.freedesktop.systemd1.Unit") {
+        Ok(properties) => dependencies::dependencies(&properties, reverse, ordering),
+        Err(error) => {
+            println!("Unable to get the dependencies of {}: {:?}", unit, error);
+            Vec::new()
+        }
+    };
+
+    for dependency in dependencies {
+        let cycle = ancestors.contains(&dependency.unit);
+        let mut relations = dependency.relations.join(", ");
+        if cycle {
+            relations.push_str(" (cycle)");
+        }
+        let state = states
+            .get(&dependency.unit)
+            .map_or("inactive", |x| x.as_str());
+        let child = store.insert_with_values(
+            Some(parent),
+            None,
+            &[0, 1, 2, 3],
+            &[&dependency.unit, &relations, &state, &cycle],
+        );
+        if !cycle {
+            store.insert_with_values(Some(&child), None, &[3], &[&true]);
+        }
+    }
+
+    store.set_value(parent, 3, &true.to_value());
+}
+
+/// Draws the laid out dependency graph onto the canvas, scaled by the zoom factor.
+fn draw_graph(cr: &cairo::Context, graph: &Graph, zoom: f64) {
+    cr.set_source_rgb(1.0, 1.0, 1.0);
+    cr.paint();
+    cr.scale(zoom, zoom);
+    cr.set_line_width(1.0);
+
+    let layout = graph.layout();
+    for edge in &graph.edges {
+        let (from, to) = graph::edge_endpoints(&layout.nodes[edge.from], &layout.nodes[edge.to]);
+        let (left, right) = graph::arrow_head(from, to);
+        let (red, green, blue) = graph::relation_color(edge.relation).1;
+        cr.set_source_rgb(red, green, blue);
+        cr.move_to(from.0, from.1);
+        cr.line_to(to.0, to.1);
+        cr.stroke();
+        cr.move_to(to.0, to.1);
+        cr.line_to(left.0, left.1);
+        cr.line_to(right.0, right.1);
+        cr.close_path();
+        cr.fill();
+    }
+
+    cr.select_font_face(
+        "monospace",
+        cairo::FontSlant::Normal,
+        cairo::FontWeight::Normal,
+    );
+    cr.set_font_size(12.0);
+    for (node, rect) in graph.nodes.iter().zip(layout.nodes.iter()) {
+        cr.rectangle(rect.x, rect.y, rect.width, rect.height);
+        cr.set_source_rgb(1.0, 1.0, 1.0);
+        cr.fill_preserve();
+        cr.set_source_rgb(0.0, 0.0, 0.0);
+        cr.stroke();
+        cr.move_to(rect.x + 10.0, rect.y + rect.height / 2.0 + 4.0);
+        cr.show_text(&node.unit);
+    }
+}
+
+/// Resizes the canvas so that the graph fits in it at the current zoom level, and redraws it.
+fn resize_graph(canvas: &gtk::DrawingArea, graph: &Graph, zoom: f64) {
+    let layout = graph.layout();
+    canvas.set_size_request(
+        (layout.width * zoom).ceil() as i32,
+        (layout.height * zoom).ceil() as i32,
+    );
+    canvas.queue_draw();
+}
+
+/// Draws the boot timeline, with the time axis stretched by the zoom level.
+fn draw_timeline(cr: &cairo::Context, timeline: &Timeline, zoom: f64) {
+    cr.set_source_rgb(1.0, 1.0, 1.0);
+    cr.paint();
+    cr.select_font_face(
+        "sans-serif",
+        cairo::FontSlant::Normal,
+        cairo::FontWeight::Normal,
+    );
+    cr.set_font_size(11.0);
+    cr.set_line_width(1.0);
+
+    let interval = timeline.tick_interval(zoom);
+    let mut second = 0;
+    while second * 1_000_000 <= timeline.end {
+        let x = timeline.x(second * 1_000_000, zoom).floor() + 0.5;
+        cr.set_source_rgb(0.87, 0.87, 0.87);
+        cr.move_to(x, timeline.y(0));
+        cr.line_to(x, timeline.y(timeline.bars.len()));
+        cr.stroke();
+        cr.set_source_rgb(0.0, 0.0, 0.0);
+        cr.move_to(x + 2.0, timeline.y(0) - 6.0);
+        cr.show_text(&format!("{}s", second));
+        second += interval;
+    }
+
+    for (row, bar) in timeline.bars.iter().enumerate() {
+        for (phase, x, y, width, height) in timeline.segment_rects(row, zoom) {
+            let (red, green, blue) = phase.color().1;
+            cr.set_source_rgb(red, green, blue);
+            cr.rectangle(x, y, width, height);
+            cr.fill();
+        }
+        let (x, y) = timeline.label_position(row, zoom);
+        cr.set_source_rgb(0.0, 0.0, 0.0);
+        cr.move_to(x, y);
+        cr.show_text(&bar.label);
+    }
+}
+
+/// Resizes the canvas so that the timeline fits in it at the current zoom level, and redraws it.
+fn resize_timeline(canvas: &gtk::DrawingArea, timeline: &Timeline, zoom: f64) {
+    canvas.set_size_request(
+        timeline.width(zoom).ceil() as i32,
+        timeline.height().ceil() as i32,
+    );
+    canvas.queue_draw();
+}
+
+/// Renders the timeline as a PNG image.
+fn timeline_png(timeline: &Timeline, zoom: f64) -> Result<Vec<u8>, String> {
+    let surface = cairo::ImageSurface::create(
+        cairo::Format::ARgb32,
+        timeline.width(zoom).ceil() as i32,
+        timeline.height().ceil() as i32,
+    )
+    .map_err(|error| format!("{:?}", error))?;
+    draw_timeline(&cairo::Context::new(&surface), timeline, zoom);
+    let mut png = Vec::new();
+    surface
+        .write_to_png(&mut png)
+        .map_err(|error| format!("{:?}", error))?;
+    Ok(png)
 }
 
-pub fn launch(config: Config) {
-    gtk::init().unwrap_or_else(|_| panic!("tv-renamer: failed to initialize GTK."));
+/// Writes the contents to a temporary file next to the path, and then moves it into place, so
+/// that the file is never left partially written.
+fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temporary = path.with_file_name(format!(".{}.tmp", name));
+    let result = fs::File::create(&temporary)
+        .and_then(|mut file| file.write_all(contents).and_then(|_| file.sync_all()))
+        .and_then(|_| fs::rename(&temporary, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temporary);
+    }
+    result
+}
+
+/// Asks the user where the contents should be saved, and writes them to the chosen file.
+fn export_file(window: &gtk::Window, title: &str, filename: &str, contents: &[u8]) {
+    let dialog = gtk::FileChooserDialog::with_buttons(
+        Some(title),
+        Some(window),
+        gtk::FileChooserAction::Save,
+        &[
+            ("Cancel", gtk::ResponseType::Cancel),
+            ("Save", gtk::ResponseType::Accept),
+        ],
+    );
+    dialog.set_current_name(filename);
+    dialog.set_do_overwrite_confirmation(true);
+    if dialog.run() == gtk::ResponseType::Accept {
+        if let Some(path) = dialog.get_filename() {
+            if let Err(message) = write_atomically(&path, contents) {
+                println!("Unable to write to file: {:?}", message);
+            }
+        }
+    }
+    dialog.close();
+}
+
+/// Adds the columns of the process list to the tree and returns its model. The hidden columns
+/// hold the CPU time and memory as numbers for sorting.
+fn setup_processes_tree(tree: &gtk::TreeView) -> gtk::ListStore {
+    let store = gtk::ListStore::new(&[
+        glib::types::Type::U32,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::U64,
+        glib::types::Type::U64,
+    ]);
+
+    let columns = [
+        ("PID", 0, Some(0)),
+        ("User", 1, Some(1)),
+        ("CPU Time", 2, Some(6)),
+        ("Memory", 3, Some(7)),
+        ("Command", 4, Some(4)),
+        ("Control Group", 5, Some(5)),
+    ];
+    add_text_columns(tree, &columns, None);
+
+    tree.set_model(Some(&store));
+    store
+}
+
+/// Lists the processes which are currently running within the control group of the unit.
+fn update_processes(store: &gtk::ListStore, handle: &DbusHandle, unit: &str) {
+    store.clear();
+    let processes = match handle.get_unit_processes(unit) {
+        Ok(processes) => processes,
+        Err(error) => {
+            println!("Unable to get the processes of {}: {:?}", unit, error);
+            return;
+        }
+    };
+    for process in processes {
+        store.insert_with_values(
+            None,
+            &[0, 1, 2, 3, 4, 5, 6, 7],
+            &[
+                &process.pid,
+                &process.user,
+                &timespan::format(process.cpu_time),
+                &status::format_bytes(process.memory),
+                &process.command,
+                &process.cgroup,
+                &process.cpu_time,
+                &process.memory,
+            ],
+        );
+    }
+}
+
+/// Draws a line chart of the values within the area, scaled so that the largest value reaches
+/// the top. The title and the description of the latest value are written above the line.
+fn draw_chart(
+    cr: &cairo::Context,
+    area: (f64, f64, f64, f64),
+    title: &str,
+    values: &[f64],
+    capacity: usize,
+    latest: &str,
+) {
+    let (x, y, width, height) = area;
+    cr.set_source_rgb(0.6, 0.6, 0.6);
+    cr.rectangle(x, y, width, height);
+    cr.stroke();
+
+    cr.set_source_rgb(0.0, 0.0, 0.0);
+    cr.move_to(x + 5.0, y + 15.0);
+    cr.show_text(&format!("{}: {}", title, latest));
+
+    if values.len() < 2 {
+        return;
+    }
+    let max = values.iter().cloned().fold(0.0, f64::max).max(1.0);
+    let step = width / (capacity.max(2) - 1) as f64;
+    let offset = x + width - step * (values.len() - 1) as f64;
+    cr.set_source_rgb(0.2, 0.4, 0.8);
+    for (index, value) in values.iter().enumerate() {
+        let point_x = offset + step * index as f64;
+        let point_y = y + height - (value / max) * (height - 20.0);
+        if index == 0 {
+            cr.move_to(point_x, point_y);
+        } else {
+            cr.line_to(point_x, point_y);
+        }
+    }
+    cr.stroke();
+}
+
+/// Draws the rolling charts of the resource usage recorded for the selected unit.
+fn draw_resources(cr: &cairo::Context, width: f64, height: f64, history: &History) {
+    cr.set_source_rgb(1.0, 1.0, 1.0);
+    cr.paint();
+    cr.set_line_width(1.0);
+    cr.set_font_size(12.0);
+
+    let latest = |counter: fn(&Sample) -> Option<u64>| {
+        history
+            .latest()
+            .and_then(counter)
+            .map_or_else(|| String::from("n/a"), status::format_bytes)
+    };
+    let rate = |values: &[f64]| match values.last() {
+        Some(&bytes) => format!("{}/s", status::format_bytes(bytes as u64)),
+        None => String::from("n/a"),
+    };
+
+    let cpu = history.cpu_percent();
+    let memory = history.values(|x| x.memory);
+    let io_read = history.rates(|x| x.io_read);
+    let io_write = history.rates(|x| x.io_write);
+    let tasks = history.values(|x| x.tasks);
+    let charts: [(&str, &[f64], String); 5] = [
+        (
+            "CPU",
+            &cpu,
+            cpu.last()
+                .map_or_else(|| String::from("n/a"), |x| format!("{:.1}%", x)),
+        ),
+        (
+            "Memory",
+            &memory,
+            format!(
+                "{} (peak: {})",
+                latest(|x| x.memory),
+                latest(|x| x.memory_peak)
+            ),
+        ),
+        ("IO Read", &io_read, rate(&io_read)),
+        ("IO Write", &io_write, rate(&io_write)),
+        (
+            "Tasks",
+            &tasks,
+            history
+                .latest()
+                .and_then(|x| x.tasks)
+                .map_or_else(|| String::from("n/a"), |x| x.to_string()),
+        ),
+    ];
+
+    let chart_height = (height - 10.0) / charts.len() as f64;
+    for (index, (title, values, description)) in charts.iter().enumerate() {
+        let area = (
+            5.0,
+            5.0 + index as f64 * chart_height,
+            width - 10.0,
+            chart_height - 5.0,
+        );
+        draw_chart(cr, area, title, values, history.capacity(), description);
+    }
+}
+
+/// Adds the columns of the `systemd-cgtop` style overview and returns its model. Every value is
+/// stored twice: as a number to sort the rows by, and as the text which is displayed.
+fn setup_cgtop_tree(tree: &gtk::TreeView) -> gtk::ListStore {
+    let store = gtk::ListStore::new(&[
+        glib::types::Type::String,
+        glib::types::Type::F64,
+        glib::types::Type::String,
+        glib::types::Type::U64,
+        glib::types::Type::String,
+        glib::types::Type::U64,
+        glib::types::Type::F64,
+        glib::types::Type::String,
+        glib::types::Type::F64,
+        glib::types::Type::String,
+    ]);
+
+    let columns = [
+        ("Control Group", 0, Some(0)),
+        ("Tasks", 5, Some(5)),
+        ("CPU", 2, Some(1)),
+        ("Memory", 4, Some(3)),
+        ("Input/s", 7, Some(6)),
+        ("Output/s", 9, Some(8)),
+    ];
+    add_text_columns(tree, &columns, None);
+
+    store.set_sort_column_id(gtk::SortColumn::Index(1), gtk::SortType::Descending);
+    tree.set_model(Some(&store));
+    store
+}
+
+/// Samples every control group and lists their usage since the previous samples.
+fn update_cgtop(store: &gtk::ListStore, previous: &RefCell<HashMap<String, Sample>>) {
+    let mut previous = previous.borrow_mut();
+    let samples = resources::read_cgroups();
+    store.clear();
+    for (unit, sample) in &samples {
+        let earlier = previous.get(unit);
+        let cpu = earlier.and_then(|x| sample.cpu_percent(x));
+        let input = earlier.and_then(|x| sample.rate(x, |x| x.io_read));
+        let output = earlier.and_then(|x| sample.rate(x, |x| x.io_write));
+        let describe = |value: Option<f64>, format: &dyn Fn(f64) -> String| {
+            value.map_or_else(|| String::from("-"), format)
+        };
+        store.insert_with_values(
+            None,
+            &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+            &[
+                unit,
+                &cpu.unwrap_or(0.0),
+                &describe(cpu, &|x| format!("{:.1}%", x)),
+                &sample.memory.unwrap_or(0),
+                &describe(sample.memory.map(|x| x as f64), &|x| {
+                    status::format_bytes(x as u64)
+                }),
+                &sample.tasks.unwrap_or(0),
+                &input.unwrap_or(0.0),
+                &describe(input, &|x| status::format_bytes(x as u64)),
+                &output.unwrap_or(0.0),
+                &describe(output, &|x| status::format_bytes(x as u64)),
+            ],
+        );
+    }
+    *previous = samples.into_iter().collect();
+}
+
+/// Adds the columns of the timers overview and returns its model. The last column of the model
+/// holds the next elapse as a number, which the rows are sorted by.
+fn setup_timers_tree(tree: &gtk::TreeView) -> gtk::ListStore {
+    let store = gtk::ListStore::new(&[
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::U64,
+    ]);
+
+    let columns = [
+        ("Next", 0, Some(6)),
+        ("Left", 1, Some(6)),
+        ("Last", 2, Some(2)),
+        ("Passed", 3, Some(2)),
+        ("Timer", 4, Some(4)),
+        ("Activates", 5, Some(5)),
+    ];
+    add_text_columns(tree, &columns, None);
+
+    store.set_sort_column_id(gtk::SortColumn::Index(6), gtk::SortType::Ascending);
+    tree.set_model(Some(&store));
+    store
+}
+
+/// Lists every loaded timer with its next and last elapse, like `systemctl list-timers`.
+fn update_timers(store: &gtk::ListStore, handle: &DbusHandle) {
+    store.clear();
+    let timers = match timers::list_timers(handle) {
+        Ok(timers) => timers,
+        Err(error) => {
+            println!("{}", error);
+            return;
+        }
+    };
+    let now = timers::now();
+    let describe =
+        |time: Option<u64>| time.map_or_else(|| String::from("-"), status::format_timestamp);
+    let span = |time: Option<u64>, suffix: &str| {
+        time.map_or_else(
+            || String::from("-"),
+            |x| {
+                format!(
+                    "{} {}",
+                    timespan::format_with_accuracy(x, 1_000_000),
+                    suffix
+                )
+            },
+        )
+    };
+    for timer in timers {
+        store.insert_with_values(
+            None,
+            &[0, 1, 2, 3, 4, 5, 6],
+            &[
+                &describe(timer.next),
+                &span(timer.left(now), "left"),
+                &describe(timer.last),
+                &span(timer.passed(now), "ago"),
+                &timer.name,
+                &timer.activates,
+                &timer.next.unwrap_or(u64::MAX),
+            ],
+        );
+    }
+}
+
+/// Adds the columns of the sockets overview and returns its model. The counters are stored
+/// both as numbers to sort the rows by and as the text which is displayed.
+fn setup_sockets_tree(tree: &gtk::TreeView) -> gtk::ListStore {
+    let store = gtk::ListStore::new(&[
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::U64,
+        glib::types::Type::String,
+        glib::types::Type::U64,
+        glib::types::Type::String,
+        glib::types::Type::U64,
+    ]);
+
+    let columns = [
+        ("Listen", 0, Some(0)),
+        ("Type", 1, Some(1)),
+        ("Socket", 2, Some(2)),
+        ("Activates", 3, Some(3)),
+        ("Accepted", 4, Some(5)),
+        ("Connections", 6, Some(7)),
+        ("Refused", 8, Some(9)),
+    ];
+    add_text_columns(tree, &columns, None);
+
+    tree.set_model(Some(&store));
+    store
+}
+
+/// Lists every loaded socket with its listen addresses and connection counters, like
+/// `systemctl list-sockets`. Sockets listening on several addresses show one per line.
+fn update_sockets(store: &gtk::ListStore, handle: &DbusHandle) {
+    store.clear();
+    let sockets = match sockets::list_sockets(handle) {
+        Ok(sockets) => sockets,
+        Err(error) => {
+            println!("{}", error);
+            return;
+        }
+    };
+    let describe = |count: Option<u64>| count.map_or_else(|| String::from("-"), |x| x.to_string());
+    for socket in sockets {
+        let addresses: Vec<&str> = socket.listen.iter().map(|x| x.1.as_str()).collect();
+        let kinds: Vec<&str> = socket.listen.iter().map(|x| x.0.as_str()).collect();
+        store.insert_with_values(
+            None,
+            &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+            &[
+                &addresses.join("\n"),
+                &kinds.join("\n"),
+                &socket.name,
+                &socket.triggers.join(", "),
+                &describe(socket.accepted),
+                &socket.accepted.unwrap_or(0),
+                &describe(socket.connections),
+                &socket.connections.unwrap_or(0),
+                &describe(socket.refused),
+                &socket.refused.unwrap_or(0),
+            ],
+        );
+    }
+}
+
+/// Adds the Time, Executable, PID, Signal and Unit columns to the crashes tree and returns its
+/// model. The hidden columns hold the cursor of each crash, its time for sorting and its stack
+/// trace.
+fn setup_crashes_tree(tree: &gtk::TreeView) -> gtk::ListStore {
+    let store = gtk::ListStore::new(&[
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::U64,
+        glib::types::Type::String,
+    ]);
+
+    let columns = [
+        ("Time", 0, Some(6)),
+        ("Executable", 1, Some(1)),
+        ("PID", 2, Some(2)),
+        ("Signal", 3, Some(3)),
+        ("Unit", 4, Some(4)),
+    ];
+    add_text_columns(tree, &columns, None);
+
+    tree.set_model(Some(&store));
+    store
+}
+
+/// Lists the most recent crashes recorded in the journal, like `coredumpctl list`.
+fn update_crashes(store: &gtk::ListStore, user: bool) {
+    store.clear();
+    let crashes = match journal::list_crashes(user, None, 500) {
+        Ok(crashes) => crashes,
+        Err(error) => {
+            println!("{}", error);
+            return;
+        }
+    };
+    for crash in crashes {
+        store.insert_with_values(
+            None,
+            &[0, 1, 2, 3, 4, 5, 6, 7],
+            &[
+                &status::format_timestamp(crash.time),
+                &crash.executable,
+                &crash.pid,
+                &crash.signal,
+                &crash.unit.as_deref().unwrap_or("-"),
+                &crash.cursor,
+                &crash.time,
+                &crash.stack_trace.as_deref().unwrap_or(""),
+            ],
+        );
+    }
+}
+
+/// Lets the user change the resource limits of a running unit. The entries start out with the
+/// current values of the unit, and only the settings which were modified are applied.
+fn run_resource_control(window: &gtk::Window, handle: &DbusHandle, unit: &str) {
+    let properties = match handle.get_unit_properties(unit, "") {
+        Ok(properties) => properties,
+        Err(error) => {
+            println!("Unable to get the properties of {}: {:?}", unit, error);
+            return;
+        }
+    };
+
+    let dialog = gtk::Dialog::with_buttons(
+        Some(format!("Resource Limits of {}", unit).as_str()),
+        Some(window),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            ("Cancel", gtk::ResponseType::Cancel),
+            ("Apply", gtk::ResponseType::Apply),
+        ],
+    );
+
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(5);
+    grid.set_column_spacing(15);
+    grid.set_border_width(5);
+    let mut entries = Vec::new();
+    for (row, control) in resources::CONTROLS.iter().enumerate() {
+        let current = control.format(properties.u64(control.property));
+        let label = gtk::Label::new(Some(control.name));
+        label.set_xalign(1f32);
+        let entry = gtk::Entry::new();
+        entry.set_text(&current);
+        grid.attach(&label, 0, row as i32, 1, 1);
+        grid.attach(&entry, 1, row as i32, 1, 1);
+        entries.push((control, current, entry));
+    }
+
+    let runtime = gtk::RadioButton::with_label("Runtime only");
+    let persistent = gtk::RadioButton::with_label_from_widget(&runtime, "Persistent");
+    let rows = resources::CONTROLS.len() as i32;
+    grid.attach(&runtime, 0, rows, 1, 1);
+    grid.attach(&persistent, 1, rows, 1, 1);
+
+    let error_label = gtk::Label::new(None);
+    grid.attach(&error_label, 0, rows + 1, 2, 1);
+
+    dialog.get_content_area().pack_start(&grid, true, true, 5);
+    dialog.show_all();
+
+    while dialog.run() == gtk::ResponseType::Apply {
+        let changes: Result<Vec<(&str, u64)>, String> = entries
+            .iter()
+            .filter(|(_, current, entry)| entry.get_text().as_str() != current)
+            .map(|(control, _, entry)| {
+                control
+                    .parse(entry.get_text().as_str())
+                    .map(|value| (control.property, value))
+            })
+            .collect();
+
+        match changes {
+            Ok(ref changes) if changes.is_empty() => break,
+            Ok(changes) => match handle.set_unit_properties(unit, runtime.get_active(), &changes) {
+                Some(error) => error_label.set_text(&error),
+                None => break,
+            },
+            Err(error) => error_label.set_text(&error),
+        }
+    }
+    dialog.close();
+}
+
+/// Edits the `OnCalendar=` settings of the timer in the buffer, showing the normalized form and
+/// the next elapses of the expression as it is written. Invalid expressions can't be applied.
+fn edit_calendar(window: &gtk::Window, buffer: &gtk::TextBuffer) {
+    let text = buffer
+        .get_text(&buffer.get_start_iter(), &buffer.get_end_iter(), true)
+        .unwrap();
+    let expressions = calendar::unit_file_expressions(text.as_str());
+
+    let dialog = gtk::Dialog::with_buttons(
+        Some("Timer Schedule"),
+        Some(window),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            ("Cancel", gtk::ResponseType::Cancel),
+            ("Apply", gtk::ResponseType::Apply),
+        ],
+    );
+    dialog.set_default_size(500, 350);
+
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(5);
+    grid.set_column_spacing(15);
+    grid.set_border_width(5);
+    let mut row = 0;
+    let mut attach = |label: &str, widget: &gtk::Widget| {
+        let label = gtk::Label::new(Some(label));
+        label.set_xalign(1f32);
+        label.set_yalign(0f32);
+        grid.attach(&label, 0, row, 1, 1);
+        grid.attach(widget, 1, row, 1, 1);
+        row += 1;
+    };
+
+    // The line of each setting in the unit file, with the new setting at the end.
+    let choice = gtk::ComboBoxText::new();
+    for (line, expression) in &expressions {
+        choice.append(Some(line.to_string().as_str()), expression);
+    }
+    choice.append(Some("new"), "New expression");
+    choice.set_active(Some(0));
+    attach("Setting", choice.upcast_ref());
+    let entry = gtk::Entry::new();
+    entry.set_hexpand(true);
+    entry.set_text(expressions.first().map_or("", |x| x.1.as_str()));
+    attach("Expression", entry.upcast_ref());
+    let normalized = gtk::Label::new(None);
+    normalized.set_xalign(0f32);
+    normalized.set_selectable(true);
+    attach("Normalized", normalized.upcast_ref());
+    let count = gtk::SpinButton::with_range(1.0, 100.0, 1.0);
+    count.set_value(5.0);
+    attach("Iterations", count.upcast_ref());
+    let elapses = gtk::Label::new(None);
+    elapses.set_xalign(0f32);
+    elapses.set_selectable(true);
+    attach("Next Elapses", elapses.upcast_ref());
+
+    dialog.get_content_area().pack_start(&grid, true, true, 5);
+    dialog.show_all();
+
+    let evaluate: Rc<dyn Fn()> = {
+        let dialog = dialog.clone();
+        let entry = entry.clone();
+        let count = count.clone();
+        Rc::new(move || match calendar::parse(entry.get_text().as_str()) {
+            Ok(spec) => {
+                normalized.set_text(&spec.normalized());
+                let now = timers::now();
+                let lines: Vec<String> = spec
+                    .next_elapses(now, count.get_value_as_int() as usize)
+                    .into_iter()
+                    .map(|elapse| {
+                        format!(
+                            "{} (in {})",
+                            status::format_timestamp(elapse),
+                            timespan::format_with_accuracy(elapse - now, 1_000_000)
+                        )
+                    })
+                    .collect();
+                if lines.is_empty() {
+                    elapses.set_text("Never");
+                } else {
+                    elapses.set_text(&lines.join("\n"));
+                }
+                dialog.set_response_sensitive(gtk::ResponseType::Apply, true);
+            }
+            Err(error) => {
+                // Expressions systemd accepts can still be saved, without a preview.
+                let reason = calendar::unsupported(entry.get_text().as_str());
+                match reason {
+                    Some(ref reason) => {
+                        normalized.set_text(&format!("Valid, but can't be previewed as {}", reason))
+                    }
+                    None => normalized.set_text(&error),
+                }
+                elapses.set_text("");
+                dialog.set_response_sensitive(gtk::ResponseType::Apply, reason.is_some());
+            }
+        })
+    };
+    evaluate();
+    {
+        let evaluate = evaluate.clone();
+        entry.connect_changed(move |_| evaluate());
+    }
+    count.connect_value_changed(move |_| evaluate());
+    {
+        let entry = entry.clone();
+        choice.connect_changed(move |choice| {
+            let line = choice.get_active_id().and_then(|x| x.parse::<usize>().ok());
+            let expression = expressions.iter().find(|x| Some(x.0) == line);
+            entry.set_text(expression.map_or("", |x| x.1.as_str()));
+        });
+    }
+
+    if dialog.run() == gtk::ResponseType::Apply {
+        let line = choice.get_active_id().and_then(|x| x.parse::<usize>().ok());
+        let expression = entry.get_text();
+        buffer.set_text(&calendar::set_unit_file_expression(
+            text.as_str(),
+            line,
+            expression.trim(),
+        ));
+    }
+    dialog.close();
+}
+
+/// The widgets of the run dialog which describe the transient unit.
+struct TransientForm {
+    name: gtk::Entry,
+    kind: gtk::ComboBoxText,
+    command: gtk::Entry,
+    environment: gtk::TextView,
+    directory: gtk::Entry,
+    user: gtk::Entry,
+    limits: Vec<(&'static resources::Control, gtk::Entry)>,
+    remain: gtk::CheckButton,
+    trigger: gtk::ComboBoxText,
+    trigger_value: gtk::Entry,
+}
+
+impl TransientForm {
+    /// Reads the transient unit described in the run dialog, checking each of its fields.
+    fn read(&self) -> Result<TransientUnit, String> {
+        let optional = |entry: &gtk::Entry| {
+            let text = entry.get_text().trim().to_owned();
+            if text.is_empty() {
+                None
+            } else {
+                Some(text)
+            }
+        };
+
+        let kind = match self.kind.get_active_id().as_deref() {
+            Some("scope") => TransientKind::Scope,
+            _ => TransientKind::Service,
+        };
+
+        let mut command = transient::split_command_line(self.command.get_text().as_str())?;
+        if kind == TransientKind::Service && !command.is_empty() {
+            command[0] = transient::find_executable(&command[0])
+                .ok_or_else(|| format!("Unable to find the command {:?}", command[0]))?;
+        }
+
+        let buffer = self.environment.get_buffer().unwrap();
+        let (start, end) = buffer.get_bounds();
+        let environment = buffer
+            .get_text(&start, &end, false)
+            .map(|x| x.to_string())
+            .unwrap_or_default()
+            .lines()
+            .map(|x| x.trim().to_owned())
+            .filter(|x| !x.is_empty())
+            .collect();
+
+        let mut parsed_limits = Vec::new();
+        for (control, entry) in &self.limits {
+            if !entry.get_text().trim().is_empty() {
+                parsed_limits.push((control.property, control.parse(entry.get_text().as_str())?));
+            }
+        }
+
+        let trigger = match (
+            self.trigger.get_active_id().as_deref(),
+            optional(&self.trigger_value),
+        ) {
+            (Some("calendar"), Some(expression)) => Some(Trigger::Calendar(expression)),
+            (Some("delay"), Some(delay)) => Some(Trigger::Delay(timespan::parse(&delay)?)),
+            (Some("calendar"), None) | (Some("delay"), None) => {
+                return Err(String::from("The trigger needs a value"))
+            }
+            _ => None,
+        };
+
+        let unit = TransientUnit {
+            name: optional(&self.name).unwrap_or_else(TransientUnit::generate_name),
+            kind,
+            command,
+            environment,
+            working_directory: optional(&self.directory),
+            user: optional(&self.user),
+            limits: parsed_limits,
+            trigger,
+            remain_after_exit: self.remain.get_active(),
+        };
+        unit.validate()?;
+        Ok(unit)
+    }
+}
+
+/// Lets the user run a command as a transient unit, like `systemd-run` does. Returns the name of
+/// the unit running the command once it was started.
+fn run_transient(window: &gtk::Window, handle: &DbusHandle) -> Option<String> {
+    let dialog = gtk::Dialog::with_buttons(
+        Some("Run Transient Unit"),
+        Some(window),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            ("Cancel", gtk::ResponseType::Cancel),
+            ("Run", gtk::ResponseType::Accept),
+        ],
+    );
+
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(5);
+    grid.set_column_spacing(15);
+    grid.set_border_width(5);
+    let mut row = 0;
+    let mut attach = |label: &str, widget: &gtk::Widget| {
+        let label = gtk::Label::new(Some(label));
+        label.set_xalign(1f32);
+        grid.attach(&label, 0, row, 1, 1);
+        grid.attach(widget, 1, row, 1, 1);
+        row += 1;
+    };
+
+    let name = gtk::Entry::new();
+    name.set_placeholder_text(Some("Generated when empty"));
+    attach("Name", name.upcast_ref());
+    let kind = gtk::ComboBoxText::new();
+    kind.append(Some("service"), "Service");
+    kind.append(Some("scope"), "Scope");
+    kind.set_active_id(Some("service"));
+    attach("Type", kind.upcast_ref());
+    let command = gtk::Entry::new();
+    command.set_hexpand(true);
+    attach("Command", command.upcast_ref());
+    let environment = gtk::TextView::new();
+    environment.set_monospace(true);
+    let environment_window =
+        gtk::ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+    environment_window.set_shadow_type(gtk::ShadowType::In);
+    environment_window.set_size_request(-1, 60);
+    environment_window.add(&environment);
+    attach("Environment", environment_window.upcast_ref());
+    let directory = gtk::Entry::new();
+    attach("Working Directory", directory.upcast_ref());
+    let user = gtk::Entry::new();
+    attach("User", user.upcast_ref());
+
+    let mut limits = Vec::new();
+    for control in resources::CONTROLS.iter() {
+        let entry = gtk::Entry::new();
+        attach(control.name, entry.upcast_ref());
+        limits.push((control, entry));
+    }
+
+    let remain = gtk::CheckButton::with_label("Keep the unit after the command exits");
+    attach("", remain.upcast_ref());
+    let trigger = gtk::ComboBoxText::new();
+    trigger.append(Some("now"), "Start now");
+    trigger.append(Some("calendar"), "On calendar event");
+    trigger.append(Some("delay"), "After a delay, such as 5min");
+    trigger.set_active_id(Some("now"));
+    attach("Trigger", trigger.upcast_ref());
+    let trigger_value = gtk::Entry::new();
+    trigger_value.set_sensitive(false);
+    attach("", trigger_value.upcast_ref());
+    {
+        let trigger_value = trigger_value.clone();
+        trigger.connect_changed(move |trigger| {
+            trigger_value.set_sensitive(trigger.get_active_id().as_deref() != Some("now"));
+        });
+    }
+
+    let error_label = gtk::Label::new(None);
+    grid.attach(&error_label, 0, row, 2, 1);
+
+    dialog.get_content_area().pack_start(&grid, true, true, 5);
+    dialog.show_all();
+
+    let form = TransientForm {
+        name,
+        kind,
+        command,
+        environment,
+        directory,
+        user,
+        limits,
+        remain,
+        trigger,
+        trigger_value,
+    };
+    let mut started = None;
+    while dialog.run() == gtk::ResponseType::Accept {
+        let unit = match form.read() {
+            Ok(unit) => unit,
+            Err(error) => {
+                error_label.set_text(&error);
+                continue;
+            }
+        };
+
+        // Scopes only group processes, so the command must be started by us beforehand.
+        let child = match unit.kind {
+            TransientKind::Scope => match unit.spawn() {
+                Ok(child) => Some(child),
+                Err(error) => {
+                    error_label.set_text(&format!("Unable to run the command: {}", error));
+                    continue;
+                }
+            },
+            TransientKind::Service => None,
+        };
+
+        match handle.start_transient_unit(&unit, child.as_ref().map(|x| x.id())) {
+            Some(error) => {
+                // The command must not keep running outside of the scope and its limits.
+                if let Some(mut child) = child {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                }
+                error_label.set_text(&error);
+            }
+            None => {
+                // GLib waits for the command once it exits, so that it doesn't linger.
+                if let Some(child) = child {
+                    glib::child_watch_add_local(glib::Pid(child.id() as i32), |_, _| ());
+                }
+                started = Some(unit.unit_name());
+                break;
+            }
+        }
+    }
+    dialog.close();
+    started
+}
+
+/// Fills the list of transient units, marking those which are currently active.
+fn update_transient_list(
+    list: &gtk::ListBox,
+    handle: &DbusHandle,
+    units: &RefCell<Vec<SystemdUnit>>,
+    icons: &RefCell<Vec<gtk::Image>>,
+) {
+    for row in list.get_children() {
+        list.remove(&row);
+    }
+    let active: Vec<String> = handle
+        .list_units()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|x| x.active_state == "active")
+        .map(|x| x.name)
+        .collect();
+
+    let mut units = units.borrow_mut();
+    let mut icons = icons.borrow_mut();
+    *units = dbus::collect_transient_units(&handle.list_unit_files());
+    icons.clear();
+    for unit in units.iter() {
+        let mut unit_row = gtk::ListBoxRow::new();
+        create_row(
+            &mut unit_row,
+            Path::new(&unit.name),
+            unit.state.clone(),
+            &mut icons,
+        );
+        update_icon(
+            icons.last().unwrap(),
+            active.iter().any(|x| x == get_filename(&unit.name)),
+        );
+        list.insert(&unit_row, -1);
+    }
+    list.show_all();
+}
+
+/// The number of journal entries read at a time.
+const JOURNAL_PAGE_SIZE: usize = 200;
+
+/// The table of the selected unit's journal, along with the entries its rows refer to.
+#[derive(Clone)]
+struct JournalView {
+    tree: gtk::TreeView,
+    store: gtk::ListStore,
+    /// Tells why the journal couldn't be read.
+    status: gtk::Label,
+    /// Every entry read so far, which each row refers to by its index in the last column.
+    entries: Rc<RefCell<Vec<Entry>>>,
+    pager: Rc<RefCell<Option<Pager>>>,
+}
+
+/// Adds the Time, Priority, PID and Message columns of the journal table and returns its model.
+/// The message is written as markup, so that search matches can be highlighted. The fifth column
+/// holds the color of the priority, and the sixth the index of the entry.
+fn setup_journal_tree(tree: &gtk::TreeView) -> gtk::ListStore {
+    let store = gtk::ListStore::new(&[
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::U32,
+    ]);
+
+    let columns = [
+        ("Time", 0, None),
+        ("Priority", 1, None),
+        ("PID", 2, None),
+        ("Message", 3, None),
+    ];
+    let columns = add_text_columns(tree, &columns, None);
+    let (ref priority, ref renderer) = columns[1];
+    priority.add_attribute(renderer, "foreground", 4);
+    let (ref message, ref renderer) = columns[3];
+    message.clear_attributes(renderer);
+    message.add_attribute(renderer, "markup", 3);
+
+    tree.set_model(Some(&store));
+    store
+}
+
+/// The color of a syslog priority in the journal table, like the colors of `journalctl`.
+fn priority_color(priority: Option<u8>) -> Option<&'static str> {
+    match priority {
+        Some(0..=3) => Some("red"),
+        Some(4) => Some("orange"),
+        Some(7) => Some("gray"),
+        _ => None,
+    }
+}
+
+/// Adds an entry to the journal table at the given row, or at the end.
+fn insert_journal_entry(journal: &JournalView, entry: Entry, position: Option<u32>) {
+    let mut entries = journal.entries.borrow_mut();
+    let priority = entry.priority();
+    journal.store.insert_with_values(
+        position,
+        &[0, 1, 2, 3, 4, 5],
+        &[
+            &journal::format_short_timestamp(entry.realtime),
+            &priority
+                .and_then(|x| journal::PRIORITIES.get(x as usize))
+                .map_or("", |x| *x),
+            &entry.field("_PID").unwrap_or(""),
+            &glib::markup_escape_text(entry.message()).as_str(),
+            &priority_color(priority),
+            &(entries.len() as u32),
+        ],
+    );
+    entries.push(entry);
+}
+
+/// Shows the most recent entries of the unit's journal which pass the filter, from the newest to
+/// the oldest. Older entries are only read once they are scrolled to.
+fn update_journal(journal: &JournalView, filter: &Filter, unit_path: &str, user: bool) {
+    journal.store.clear();
+    journal.entries.borrow_mut().clear();
+    journal.status.set_text("");
+    match Pager::open(get_filename(unit_path), user, filter) {
+        Ok(pager) => {
+            *journal.pager.borrow_mut() = Some(pager);
+            load_older_journal(journal);
+        }
+        Err(error) => {
+            *journal.pager.borrow_mut() = None;
+            journal.status.set_text(&error);
+        }
+    }
+}
+
+/// Appends the next page of older entries to the end of the journal table, once the entries
+/// already shown have been scrolled through.
+fn load_older_journal(journal: &JournalView) {
+    // The pager is released before inserting, as the table may scroll and read again.
+    let entries = match journal
+        .pager
+        .borrow_mut()
+        .as_mut()
+        .map(|x| x.older(JOURNAL_PAGE_SIZE))
+    {
+        Some(Ok(entries)) => entries,
+        Some(Err(error)) => {
+            println!("{}", error);
+            return;
+        }
+        None => return,
+    };
+    for entry in entries {
+        insert_journal_entry(journal, entry, None);
+    }
+}
+
+/// Inserts the entries written since the newest entry shown at the top of the journal table.
+/// The table keeps showing the newest entries, unless it was scrolled away from them, in which
+/// case it stays where it was.
+fn load_newer_journal(journal: &JournalView) {
+    let entries = match journal
+        .pager
+        .borrow_mut()
+        .as_mut()
+        .map(|x| x.newer(JOURNAL_PAGE_SIZE))
+    {
+        Some(Ok(entries)) => entries,
+        Some(Err(error)) => {
+            println!("{}", error);
+            return;
+        }
+        None => return,
+    };
+    if entries.is_empty() {
+        return;
+    }
+    let top = if journal.tree.get_vadjustment().unwrap().get_value() > 0.0 {
+        journal
+            .tree
+            .get_visible_range()
+            .map(|(start, _)| start.get_indices()[0])
+    } else {
+        None
+    };
+    let count = entries.len() as i32;
+    for entry in entries {
+        insert_journal_entry(journal, entry, Some(0));
+    }
+    if let Some(top) = top {
+        let path = gtk::TreePath::from_indicesv(&[top + count]);
+        journal
+            .tree
+            .scroll_to_cell(Some(&path), None::<&gtk::TreeViewColumn>, true, 0.0, 0.0);
+    }
+}
+
+/// Adds the Field and Value columns of the journal entry inspector and returns its model.
+fn setup_journal_fields_tree(tree: &gtk::TreeView) -> gtk::ListStore {
+    let store = gtk::ListStore::new(&[glib::types::Type::String, glib::types::Type::String]);
+
+    add_text_columns(tree, &[("Field", 0, None), ("Value", 1, None)], None);
+
+    tree.set_model(Some(&store));
+    store
+}
+
+/// Lists every field of the journal entry, in the order they are stored.
+fn update_journal_fields(store: &gtk::ListStore, entry: &Entry) {
+    store.clear();
+    for (field, value) in &entry.fields {
+        store.insert_with_values(None, &[0, 1], &[field, value]);
+    }
+}
+
+/// Asks which format the unit's journal should be exported in, previewing the size of the file,
+/// and saves every entry which passes the filter in that format.
+fn export_journal(window: &gtk::Window, unit: &str, user: bool, filter: &Filter) {
+    let entries = match journal::read_entries(unit, user, filter) {
+        Ok(entries) => Rc::new(entries),
+        Err(error) => {
+            println!("{}", error);
+            return;
+        }
+    };
+
+    let dialog = gtk::Dialog::with_buttons(
+        Some("Export Journal"),
+        Some(window),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            ("Cancel", gtk::ResponseType::Cancel),
+            ("Save…", gtk::ResponseType::Accept),
+        ],
+    );
+
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(5);
+    grid.set_column_spacing(15);
+    grid.set_border_width(5);
+    let format = gtk::ComboBoxText::new();
+    for output in OutputFormat::ALL.iter() {
+        format.append(
+            Some(output.name()),
+            &format!("{} ({})", output.description(), output.name()),
+        );
+    }
+    format.set_active(Some(0));
+    format.set_hexpand(true);
+    let size = gtk::Label::new(None);
+    size.set_xalign(0f32);
+    for (row, &(label, widget)) in [
+        ("Format", format.upcast_ref::<gtk::Widget>()),
+        ("Size", size.upcast_ref()),
+    ]
+    .iter()
+    .enumerate()
+    {
+        let label = gtk::Label::new(Some(label));
+        label.set_xalign(1f32);
+        grid.attach(&label, 0, row as i32, 1, 1);
+        grid.attach(widget, 1, row as i32, 1, 1);
+    }
+    dialog.get_content_area().pack_start(&grid, true, true, 5);
+
+    // The contents of the file in the chosen format, written ahead to tell how large it is.
+    let contents: Rc<RefCell<Vec<u8>>> = Rc::new(RefCell::new(Vec::new()));
+    let chosen = {
+        let format = format.clone();
+        move || OutputFormat::ALL[format.get_active().unwrap_or(0) as usize]
+    };
+    let preview: Rc<dyn Fn()> = {
+        let contents = contents.clone();
+        let chosen = chosen.clone();
+        Rc::new(move || {
+            let output = chosen().write(&entries);
+            size.set_text(&format!(
+                "{} entries, {}",
+                entries.len(),
+                status::format_bytes(output.len() as u64)
+            ));
+            *contents.borrow_mut() = output;
+        })
+    };
+    preview();
+    format.connect_changed(move |_| preview());
+
+    dialog.show_all();
+    let response = dialog.run();
+    dialog.close();
+    if response == gtk::ResponseType::Accept {
+        let filename = format!("{}.{}", unit, chosen().extension());
+        export_file(window, "Export Journal", &filename, &contents.borrow());
+    }
+}
+
+/// Adds the columns of the runs of a unit and returns its model. The seventh column of the model
+/// holds the color of the run, which highlights the runs that failed.
+fn setup_invocations_tree(tree: &gtk::TreeView) -> gtk::ListStore {
+    let store = gtk::ListStore::new(&[
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::String,
+        glib::types::Type::U32,
+        glib::types::Type::String,
+    ]);
+
+    let columns = [
+        ("Started", 0, None),
+        ("Last Entry", 1, None),
+        ("Duration", 2, None),
+        ("Result", 3, None),
+        ("Main Process", 4, None),
+        ("Entries", 5, None),
+    ];
+    add_text_columns(tree, &columns, Some(6));
+
+    tree.set_model(Some(&store));
+    store
+}
+
+/// Lists the runs of a unit, from the most recent.
+fn update_invocations(store: &gtk::ListStore, invocations: &[Invocation]) {
+    store.clear();
+    for (index, invocation) in invocations.iter().enumerate() {
+        // Only the latest run may still be going on.
+        let result = match invocation.result {
+            Some(ref result) => result.as_str(),
+            None if index == 0 => "running",
+            None => "unknown",
+        };
+        let color = invocation
+            .result
+            .as_ref()
+            .filter(|x| *x != "success")
+            .map(|_| "red");
+        store.insert_with_values(
+            None,
+            &[0, 1, 2, 3, 4, 5, 6],
+            &[
+                &status::format_timestamp(invocation.started),
+                &status::format_timestamp(invocation.last),
+                &timespan::format_with_accuracy(invocation.last - invocation.started, 1000),
+                &result,
+                &invocation.exit.as_deref().unwrap_or("-"),
+                &(invocation.entries as u32),
+                &color,
+            ],
+        );
+    }
+}
+
+/// Shows the entries of two runs of a unit side by side, timed from the start of each run.
+fn compare_invocations(
+    window: &gtk::Window,
+    unit: &str,
+    user: bool,
+    filter: &Filter,
+    runs: [&Invocation; 2],
+) {
+    let mut transcripts = Vec::new();
+    for run in runs.iter() {
+        match run.transcript(unit, user, filter.boot.as_deref()) {
+            Ok(transcript) => transcripts.push(transcript),
+            Err(error) => {
+                println!("{}", error);
+                return;
+            }
+        }
+    }
+
+    let dialog = gtk::Dialog::with_buttons(
+        Some("Compare Runs"),
+        Some(window),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[("Close", gtk::ResponseType::Close)],
+    );
+    dialog.set_default_size(1000, 500);
+
+    let paned = gtk::Paned::new(gtk::Orientation::Horizontal);
+    for (index, run) in runs.iter().enumerate() {
+        let header = gtk::Label::new(Some(&format!(
+            "Started {} · {}",
+            status::format_timestamp(run.started),
+            run.result.as_deref().unwrap_or("running")
+        )));
+        header.set_xalign(0f32);
+        let transcript = gtk::TextView::new();
+        transcript.set_monospace(true);
+        transcript.set_editable(false);
+        transcript
+            .get_buffer()
+            .unwrap()
+            .set_text(&transcripts[index]);
+        let scroller = gtk::ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+        scroller.set_shadow_type(gtk::ShadowType::In);
+        scroller.add(&transcript);
+        let column = gtk::Box::new(gtk::Orientation::Vertical, 5);
+        column.pack_start(&header, false, true, 0);
+        column.pack_start(&scroller, true, true, 0);
+        if index == 0 {
+            paned.pack1(&column, true, false);
+        } else {
+            paned.pack2(&column, true, false);
+        }
+    }
+    dialog.get_content_area().pack_start(&paned, true, true, 5);
+    dialog.show_all();
+    dialog.run();
+    dialog.close();
+}
+
+/// Lists the runs of the unit during the filter's boot, along with how each of them ended. Two
+/// runs can be compared side by side, and the run chosen to be shown in the journal is returned.
+fn browse_invocations(
+    window: &gtk::Window,
+    unit: &str,
+    user: bool,
+    filter: &Filter,
+) -> Option<String> {
+    let invocations = match journal::list_invocations(unit, user, filter) {
+        Ok(invocations) => invocations,
+        Err(error) => {
+            println!("{}", error);
+            return None;
+        }
+    };
+
+    const COMPARE: gtk::ResponseType = gtk::ResponseType::Other(1);
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&format!("Runs of {}", unit)),
+        Some(window),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            ("Close", gtk::ResponseType::Close),
+            ("Compare", COMPARE),
+            ("Show in Journal", gtk::ResponseType::Apply),
+        ],
+    );
+    dialog.set_default_size(800, 350);
+
+    let tree = gtk::TreeView::new();
+    let store = setup_invocations_tree(&tree);
+    update_invocations(&store, &invocations);
+    let selection = tree.get_selection();
+    selection.set_mode(gtk::SelectionMode::Multiple);
+    let scroller = gtk::ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+    scroller.set_shadow_type(gtk::ShadowType::In);
+    scroller.add(&tree);
+    dialog
+        .get_content_area()
+        .pack_start(&scroller, true, true, 5);
+
+    // A single run can be shown, and two runs compared.
+    {
+        let dialog = dialog.clone();
+        selection.connect_changed(move |selection| {
+            let count = selection.count_selected_rows();
+            dialog.set_response_sensitive(gtk::ResponseType::Apply, count == 1);
+            dialog.set_response_sensitive(COMPARE, count == 2);
+        });
+    }
+    dialog.show_all();
+    dialog.set_response_sensitive(gtk::ResponseType::Apply, false);
+    dialog.set_response_sensitive(COMPARE, false);
+    // The latest run is the one most often looked at.
+    if let Some(latest) = store.get_iter_first() {
+        selection.select_iter(&latest);
+    }
+
+    let selected = || -> Vec<&Invocation> {
+        let (paths, _) = selection.get_selected_rows();
+        paths
+            .iter()
+            .filter_map(|x| invocations.get(x.get_indices()[0] as usize))
+            .collect()
+    };
+    let mut chosen = None;
+    loop {
+        match dialog.run() {
+            COMPARE => {
+                if let [newer, older] = selected().as_slice() {
+                    compare_invocations(dialog.upcast_ref(), unit, user, filter, [older, newer]);
+                }
+            }
+            gtk::ResponseType::Apply => {
+                chosen = selected().first().map(|x| x.id.clone());
+                break;
+            }
+            _ => break,
+        }
+    }
+    dialog.close();
+    chosen
+}
+
+fn get_filename(path: &str) -> &str {
+    let filename = Path::new(path)
+        .file_name()
+        .unwrap_or_else(|| panic!("Couldn't get filename of path {:?}", path));
+    filename
+        .to_str()
+        .unwrap_or_else(|| panic!("Filename {:?} wasn't valid unicode", filename))
+}
+
+/// Collects the filenames of the units behind every selected row of the list, along with the
+/// status icons of those rows.
+fn selected_units(
+    list: &gtk::ListBox,
+    units: &[SystemdUnit],
+    icons: &[gtk::Image],
+) -> Vec<(String, gtk::Image)> {
+    list.get_selected_rows()
+        .iter()
+        .map(|row| row.get_index() as usize)
+        .map(|index| {
+            (
+                get_filename(&units[index].name).to_owned(),
+                icons[index].clone(),
+            )
+        })
+        .collect()
+}
+
+/// Returns the index of the selected row, or `None` unless exactly one row is selected, so that
+/// the actions on a single unit don't pick one of several selected units at random.
+fn selected_index(list: &gtk::ListBox) -> Option<usize> {
+    match list.get_selected_rows().as_slice() {
+        [row] => Some(row.get_index() as usize),
+        _ => None,
+    }
+}
+
+/// Applies the action to each unit in turn while a modal dialog reports the progress. Once every
+/// unit has been processed, the dialog lists which units succeeded and which failed, and why.
+/// The status icons of the units which succeeded are set to `state`, if the action changes what
+/// the icons show.
+fn run_batch(
+    window: &gtk::Window,
+    handle: Rc<DbusHandle>,
+    action: BatchAction,
+    units: Vec<(String, gtk::Image)>,
+    state: Option<bool>,
+) {
+    if units.is_empty() {
+        return;
+    }
+
+    let dialog = gtk::Dialog::with_buttons(
+        Some(format!("{} {} units", action.label(), units.len()).as_str()),
+        Some(window),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[("Close", gtk::ResponseType::Close)],
+    );
+    dialog.set_default_size(500, 300);
+    dialog.set_response_sensitive(gtk::ResponseType::Close, false);
+    dialog.connect_response(|dialog, _| dialog.close());
+
+    let progress = gtk::ProgressBar::new();
+    progress.set_show_text(true);
+    let report = gtk::TextView::new();
+    report.set_editable(false);
+    report.set_monospace(true);
+    let scroller = gtk::ScrolledWindow::new(None::<&gtk::Adjustment>, None::<&gtk::Adjustment>);
+    scroller.add(&report);
+
+    let content = dialog.get_content_area();
+    content.pack_start(&progress, false, true, 5);
+    content.pack_start(&scroller, true, true, 5);
+    dialog.show_all();
+
+    let total = units.len();
+    let mut pending = units.into_iter().enumerate();
+    let mut succeeded = Vec::new();
+    let mut failed = Vec::new();
+    glib::idle_add_local(move || match pending.next() {
+        Some((index, (unit, icon))) => {
+            progress.set_text(Some(unit.as_str()));
+            match action.apply(&handle, &unit) {
+                None => {
+                    if let Some(state) = state {
+                        update_icon(&icon, state);
+                    }
+                    succeeded.push(unit);
+                }
+                Some(error) => failed.push((unit, error)),
+            }
+            progress.set_fraction((index + 1) as f64 / total as f64);
+            glib::Continue(true)
+        }
+        None => {
+            progress.set_text(Some("Done"));
+            let mut summary = format!("Succeeded ({}):\n", succeeded.len());
+            for unit in &succeeded {
+                summary.push_str(&format!("    {}\n", unit));
+            }
+            summary.push_str(&format!("\nFailed ({}):\n", failed.len()));
+            for (unit, error) in &failed {
+                summary.push_str(&format!("    {}: {}\n", unit, error));
+            }
+            report.get_buffer().unwrap().set_text(summary.as_str());
+            dialog.set_response_sensitive(gtk::ResponseType::Close, true);
+            glib::Continue(false)
+        }
+    });
+}
+
+pub fn launch(config: Config) {
+    gtk::init().unwrap_or_else(|_| panic!("tv-renamer: failed to initialize GTK."));
+
+    let builder = gtk::Builder::from_string(include_str!("interface.glade"));
+    let window: gtk::Window = builder.get_object("main_window").unwrap();
+    let unit_stack: gtk::Stack = builder.get_object("unit_stack").unwrap();
+    let services_list: gtk::ListBox = builder.get_object("services_list").unwrap();
+    let sockets_list: gtk::ListBox = builder.get_object("sockets_list").unwrap();
+    let timers_list: gtk::ListBox = builder.get_object("timers_list").unwrap();
+    let transient_list: gtk::ListBox = builder.get_object("transient_list").unwrap();
+    let unit_info: gtk::TextView = builder.get_object("unit_info").unwrap();
+    let ablement_switch: gtk::Switch = builder.get_object("ablement_switch").unwrap();
+    let start_button: gtk::Button = builder.get_object("start_button").unwrap();
+    let stop_button: gtk::Button = builder.get_object("stop_button").unwrap();
+    let save_unit_file: gtk::Button = builder.get_object("save_button").unwrap();
+    let calendar_button: gtk::Button = builder.get_object("calendar_button").unwrap();
+    let unit_file_error: gtk::Label = builder.get_object("unit_file_error_label").unwrap();
+    let unit_menu_label: gtk::Label = builder.get_object("unit_menu_label").unwrap();
+    let unit_popover: gtk::PopoverMenu = builder.get_object("unit_menu_popover").unwrap();
+    let services_button: gtk::Button = builder.get_object("services_button").unwrap();
+    let sockets_button: gtk::Button = builder.get_object("sockets_button").unwrap();
+    let timers_button: gtk::Button = builder.get_object("timers_button").unwrap();
+    let transient_button: gtk::Button = builder.get_object("transient_button").unwrap();
+    let unit_journal = {
+        let tree: gtk::TreeView = builder.get_object("unit_journal_view").unwrap();
+        JournalView {
+            store: setup_journal_tree(&tree),
+            tree,
+            status: builder.get_object("journal_status_label").unwrap(),
+            entries: Rc::new(RefCell::new(Vec::new())),
+            pager: Rc::new(RefCell::new(None)),
+        }
+    };
+    // Narrows down the entries shown in the journal view.
+    let journal_filter: Rc<RefCell<Filter>> = Rc::new(RefCell::new(Filter::default()));
+    let refresh_log_button: gtk::Button = builder.get_object("refresh_log_button").unwrap();
+    let follow_log_button: gtk::ToggleButton = builder.get_object("follow_log_button").unwrap();
+    let right_header: gtk::Label = builder.get_object("header_service_label").unwrap();
+    let info_stack: gtk::Stack = builder.get_object("info_stack").unwrap();
+    let status_grid: gtk::Grid = builder.get_object("unit_status_grid").unwrap();
+    let dependency_tree: gtk::TreeView = builder.get_object("dependencies_tree").unwrap();
+    let dependency_store = setup_dependency_tree(&dependency_tree);
+    let processes_tree: gtk::TreeView = builder.get_object("processes_tree").unwrap();
+    let processes_store = setup_processes_tree(&processes_tree);
+    let resources_canvas: gtk::DrawingArea = builder.get_object("resources_canvas").unwrap();
+    let resources_history = Rc::new(RefCell::new(History::new(60)));
+    let cgtop_tree: gtk::TreeView = builder.get_object("cgtop_tree").unwrap();
+    let cgtop_store = setup_cgtop_tree(&cgtop_tree);
+    let cgtop_samples = Rc::new(RefCell::new(HashMap::new()));
+    let reverse_toggle: gtk::CheckButton =
+        builder.get_object("dependencies_reverse_toggle").unwrap();
+    let ordering_toggle: gtk::CheckButton =
+        builder.get_object("dependencies_ordering_toggle").unwrap();
+
+    // The filename of the unit whose information is currently being displayed.
+    let selected_unit: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+
+    {
+        // NOTE: Services Menu Button
+        let label = unit_menu_label.clone();
+        let stack = unit_stack.clone();
+        let popover = unit_popover.clone();
+        services_button.connect_clicked(move |_| {
+            stack.set_visible_child_name("Services");
+            label.set_text("Services");
+            popover.set_visible(false);
+        });
+    }
+
+    {
+        // NOTE: Sockets Menu Button
+        let label = unit_menu_label.clone();
+        let stack = unit_stack.clone();
+        let popover = unit_popover.clone();
+        sockets_button.connect_clicked(move |_| {
+            stack.set_visible_child_name("Sockets");
+            label.set_text("Sockets");
+            popover.set_visible(false);
+        });
+    }
+
+    {
+        // NOTE: Timers Menu Button
+        let label = unit_menu_label.clone();
+        let stack = unit_stack.clone();
+        let popover = unit_popover.clone();
+        timers_button.connect_clicked(move |_| {
+            stack.set_visible_child_name("Timers");
+            label.set_text("Timers");
+            popover.set_visible(false);
+        });
+    }
+
+    {
+        // NOTE: Transient Menu Button
+        let label = unit_menu_label.clone();
+        let stack = unit_stack.clone();
+        let popover = unit_popover.clone();
+        transient_button.connect_clicked(move |_| {
+            stack.set_visible_child_name("Transient");
+            label.set_text("Transient");
+            popover.set_visible(false);
+        });
+    }
+
+    let handle = dbus::DbusHandle::new(config.bus_type);
+    let handle = Rc::new(handle);
+    let usermode = config.user();
+
+    // Setup the Analyze stack
+    let boot_history = BootHistory::open(usermode);
+    setup_systemd_analyze(&builder, &handle, &boot_history);
+
+    {
+        // NOTE: Boot History
+        let canvas: gtk::DrawingArea = builder.get_object("boot_history_canvas").unwrap();
+        let before_combo: gtk::ComboBoxText = builder.get_object("history_before_combo").unwrap();
+        let after_combo: gtk::ComboBoxText = builder.get_object("history_after_combo").unwrap();
+        let tree: gtk::TreeView = builder.get_object("history_delta_tree").unwrap();
+        let store = setup_boot_delta_tree(&tree);
+        let records = Rc::new(boot_history.load());
+        let count = records.len() as u32;
+
+        {
+            let records = records.clone();
+            canvas.connect_draw(move |canvas, cr| {
+                let width = f64::from(canvas.get_allocated_width());
+                let height = f64::from(canvas.get_allocated_height());
+                draw_boot_history(cr, width, height, &records);
+                gtk::Inhibit(false)
+            });
+        }
+
+        for record in records.iter() {
+            let label = format!(
+                "{} ({})",
+                status::format_timestamp(record.started),
+                timespan::format(record.total())
+            );
+            before_combo.append(Some(&record.id), &label);
+            after_combo.append(Some(&record.id), &label);
+        }
+
+        let compare_boots: Rc<dyn Fn()> = {
+            let before_combo = before_combo.clone();
+            let after_combo = after_combo.clone();
+            Rc::new(move || {
+                let find = |combo: &gtk::ComboBoxText| {
+                    let id = combo.get_active_id()?;
+                    records.iter().find(|x| x.id == id.as_str())
+                };
+                match (find(&before_combo), find(&after_combo)) {
+                    (Some(before), Some(after)) => update_boot_deltas(&store, before, after),
+                    _ => store.clear(),
+                }
+            })
+        };
+        for combo in [&before_combo, &after_combo].iter() {
+            let compare_boots = compare_boots.clone();
+            combo.connect_changed(move |_| compare_boots());
+        }
+
+        // Compare the two most recent boots by default.
+        if count >= 2 {
+            before_combo.set_active(Some(count - 2));
+            after_combo.set_active(Some(count - 1));
+        }
+    }
+
+    {
+        // NOTE: Boot Timeline
+        let canvas: gtk::DrawingArea = builder.get_object("timeline_canvas").unwrap();
+        let views: gtk::Stack = builder.get_object("analyze_views_stack").unwrap();
+        let timeline = Rc::new(RefCell::new(Timeline::default()));
+        let zoom = Rc::new(Cell::new(1.0));
+
+        {
+            let timeline = timeline.clone();
+            let zoom = zoom.clone();
+            canvas.connect_draw(move |_, cr| {
+                draw_timeline(cr, &timeline.borrow(), zoom.get());
+                gtk::Inhibit(false)
+            });
+        }
+
+        {
+            let timeline = timeline.clone();
+            canvas.connect_query_tooltip(move |_, _, y, _, tooltip| {
+                match timeline.borrow().bar_at(f64::from(y)) {
+                    Some(bar) => {
+                        tooltip.set_text(Some(&bar.describe()));
+                        true
+                    }
+                    None => false,
+                }
+            });
+        }
+
+        // Collects the timestamps of every loaded unit again.
+        let rebuild_timeline: Rc<dyn Fn()> = {
+            let canvas = canvas.clone();
+            let timeline = timeline.clone();
+            let zoom = zoom.clone();
+            let handle = handle.clone();
+            Rc::new(move || match Timeline::build(&handle) {
+                Ok(built) => {
+                    *timeline.borrow_mut() = built;
+                    resize_timeline(&canvas, &timeline.borrow(), zoom.get());
+                }
+                Err(error) => println!("{}", error),
+            })
+        };
+
+        {
+            // The timeline takes a request per loaded unit, so it is only built once viewed.
+            let timeline = timeline.clone();
+            let rebuild_timeline = rebuild_timeline.clone();
+            views.connect_property_visible_child_notify(move |views| {
+                if timeline.borrow().bars.is_empty()
+                    && views.get_visible_child_name().as_deref() == Some("Timeline")
+                {
+                    rebuild_timeline();
+                }
+            });
+        }
+
+        let refresh_button: gtk::Button = builder.get_object("timeline_refresh_button").unwrap();
+        refresh_button.connect_clicked(move |_| rebuild_timeline());
+
+        for &(id, factor) in [
+            ("timeline_zoom_out_button", 0.8),
+            ("timeline_zoom_reset_button", 0.0),
+            ("timeline_zoom_in_button", 1.25),
+        ]
+        .iter()
+        {
+            let button: gtk::Button = builder.get_object(id).unwrap();
+            let canvas = canvas.clone();
+            let timeline = timeline.clone();
+            let zoom = zoom.clone();
+            button.connect_clicked(move |_| {
+                // A factor of zero resets the zoom level.
+                zoom.set(if factor == 0.0 {
+                    1.0
+                } else {
+                    zoom.get() * factor
+                });
+                resize_timeline(&canvas, &timeline.borrow(), zoom.get());
+            });
+        }
+
+        for &extension in ["svg", "png"].iter() {
+            let button: gtk::Button = builder
+                .get_object(&format!("timeline_export_{}_button", extension))
+                .unwrap();
+            let window = window.clone();
+            let timeline = timeline.clone();
+            let zoom = zoom.clone();
+            button.connect_clicked(move |_| {
+                let timeline = timeline.borrow();
+                if timeline.bars.is_empty() {
+                    return;
+                }
+                let contents = match extension {
+                    "svg" => timeline.to_svg(zoom.get()).into_bytes(),
+                    _ => match timeline_png(&timeline, zoom.get()) {
+                        Ok(png) => png,
+                        Err(error) => {
+                            println!("Unable to render the timeline: {}", error);
+                            return;
+                        }
+                    },
+                };
+                let filename = format!("boot.{}", extension);
+                export_file(&window, "Export Timeline", &filename, &contents);
+            });
+        }
+    }
+
+    {
+        // NOTE: Critical Chain
+        let views: gtk::Stack = builder.get_object("analyze_views_stack").unwrap();
+        let entry: gtk::Entry = builder.get_object("critical_chain_entry").unwrap();
+        let button: gtk::Button = builder.get_object("critical_chain_button").unwrap();
+        let tree: gtk::TreeView = builder.get_object("critical_chain_tree").unwrap();
+        let store = setup_critical_chain_tree(&tree);
+        let analyze_chain: Rc<dyn Fn()> = {
+            let entry = entry.clone();
+            let handle = handle.clone();
+            Rc::new(move || {
+                let unit = entry.get_text();
+                if !unit.trim().is_empty() {
+                    update_critical_chain(&tree, &store, &handle, unit.trim());
+                }
+            })
+        };
+
+        let analyze = analyze_chain.clone();
+        button.connect_clicked(move |_| analyze());
+        let analyze = analyze_chain.clone();
+        entry.connect_activate(move |_| analyze());
+
+        // The chain takes a request per unit along the way, so it is only computed once viewed.
+        let analyzed = Cell::new(false);
+        views.connect_property_visible_child_notify(move |views| {
+            if !analyzed.get()
+                && views.get_visible_child_name().as_deref() == Some("Critical Chain")
+            {
+                analyzed.set(true);
+                analyze_chain();
+            }
+        });
+    }
+
+    {
+        // NOTE: Security
+        let tree: gtk::TreeView = builder.get_object("security_tree").unwrap();
+        let store = setup_security_tree(&tree);
+        let findings_tree: gtk::TreeView = builder.get_object("security_findings_tree").unwrap();
+        let findings_store = setup_security_findings_tree(&findings_tree);
+        let unit_label: gtk::Label = builder.get_object("security_unit_label").unwrap();
+        let refresh_button: gtk::Button = builder.get_object("security_refresh_button").unwrap();
+        let override_button: gtk::Button = builder.get_object("security_override_button").unwrap();
+        let assessments: Rc<RefCell<Vec<Assessment>>> = Rc::new(RefCell::new(Vec::new()));
+        // The service whose breakdown is currently shown.
+        let selected: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+
+        let assess: Rc<dyn Fn()> = {
+            let assessments = assessments.clone();
+            let handle = handle.clone();
+            Rc::new(move || match security::assess_services(&handle) {
+                Ok(results) => {
+                    update_security(&store, &results);
+                    *assessments.borrow_mut() = results;
+                }
+                Err(error) => println!("{}", error),
+            })
+        };
+
+        {
+            let findings_store = findings_store.clone();
+            let override_button = override_button.clone();
+            let selected = selected.clone();
+            tree.get_selection().connect_changed(move |selection| {
+                findings_store.clear();
+                let unit = selection
+                    .get_selected()
+                    .and_then(|(model, iter)| model.get_value(&iter, 0).get::<String>().unwrap());
+                let assessments = assessments.borrow();
+                let assessment = unit
+                    .as_ref()
+                    .and_then(|unit| assessments.iter().find(|x| &x.unit == unit));
+                match assessment {
+                    Some(assessment) => {
+                        update_security_findings(&findings_store, assessment);
+                        unit_label.set_text(&format!(
+                            "{}: {:.1} {}",
+                            assessment.unit,
+                            assessment.exposure,
+                            assessment.level()
+                        ));
+                    }
+                    None => unit_label.set_text(""),
+                }
+                override_button.set_sensitive(assessment.is_some());
+                *selected.borrow_mut() = assessment.map(|x| x.unit.clone());
+            });
+        }
+
+        let refresh = assess.clone();
+        refresh_button.connect_clicked(move |_| refresh());
+
+        {
+            let window = window.clone();
+            let handle = handle.clone();
+            let assess = assess.clone();
+            override_button.connect_clicked(move |_| {
+                let unit = match selected.borrow().clone() {
+                    Some(unit) => unit,
+                    None => return,
+                };
+                let mut suggestions = Vec::new();
+                if let Some(iter) = findings_store.get_iter_first() {
+                    loop {
+                        if findings_store
+                            .get_value(&iter, 0)
+                            .get_some::<bool>()
+                            .unwrap()
+                        {
+                            if let Ok(Some(suggestion)) =
+                                findings_store.get_value(&iter, 4).get::<String>()
+                            {
+                                suggestions.push(suggestion);
+                            }
+                        }
+                        if !findings_store.iter_next(&iter) {
+                            break;
+                        }
+                    }
+                }
+                if create_security_override(&window, &handle, &unit, &suggestions, usermode) {
+                    assess();
+                }
+            });
+        }
+
+        // Assessing takes a request per service, so it is only done once viewed.
+        let assessed = Cell::new(false);
+        info_stack.connect_property_visible_child_notify(move |stack| {
+            if !assessed.get() && stack.get_visible_child_name().as_deref() == Some("Security") {
+                assessed.set(true);
+                assess();
+            }
+        });
+    }
+
+    {
+        // NOTE: Timers
+        let tree: gtk::TreeView = builder.get_object("timers_tree").unwrap();
+        let store = setup_timers_tree(&tree);
+        let refresh_button: gtk::Button = builder.get_object("timers_refresh_button").unwrap();
+        let trigger_button: gtk::Button = builder.get_object("timers_trigger_button").unwrap();
+
+        {
+            let trigger_button = trigger_button.clone();
+            tree.get_selection().connect_changed(move |selection| {
+                trigger_button.set_sensitive(selection.get_selected().is_some());
+            });
+        }
+
+        {
+            let store = store.clone();
+            let handle = handle.clone();
+            refresh_button.connect_clicked(move |_| update_timers(&store, &handle));
+        }
+
+        {
+            let store = store.clone();
+            let handle = handle.clone();
+            trigger_button.connect_clicked(move |_| {
+                let unit = tree
+                    .get_selection()
+                    .get_selected()
+                    .and_then(|(model, iter)| model.get_value(&iter, 5).get::<String>().unwrap());
+                if let Some(unit) = unit.filter(|x| !x.is_empty()) {
+                    if handle.start_unit(&unit).is_none() {
+                        update_timers(&store, &handle);
+                    }
+                }
+            });
+        }
+
+        // Listing the timers takes a request per timer, so it is only done when they are shown.
+        let handle = handle.clone();
+        info_stack.connect_property_visible_child_notify(move |stack| {
+            if stack.get_visible_child_name().as_deref() == Some("Timers") {
+                update_timers(&store, &handle);
+            }
+        });
+    }
+
+    {
+        // NOTE: Sockets
+        let tree: gtk::TreeView = builder.get_object("sockets_tree").unwrap();
+        let store = setup_sockets_tree(&tree);
+        let refresh_button: gtk::Button = builder.get_object("sockets_refresh_button").unwrap();
+
+        {
+            let store = store.clone();
+            let handle = handle.clone();
+            refresh_button.connect_clicked(move |_| update_sockets(&store, &handle));
+        }
+
+        // Listing the sockets takes a request per socket, so it is only done when they are shown.
+        let handle = handle.clone();
+        info_stack.connect_property_visible_child_notify(move |stack| {
+            if stack.get_visible_child_name().as_deref() == Some("Sockets") {
+                update_sockets(&store, &handle);
+            }
+        });
+    }
+
+    let show_crash: Rc<dyn Fn(&str)> = {
+        // NOTE: Crashes
+        let tree: gtk::TreeView = builder.get_object("crashes_tree").unwrap();
+        let store = setup_crashes_tree(&tree);
+        let refresh_button: gtk::Button = builder.get_object("crashes_refresh_button").unwrap();
+        let stack_trace_view: gtk::TextView = builder.get_object("crash_stack_trace_view").unwrap();
+
+        tree.get_selection().connect_changed(move |selection| {
+            let stack_trace = selection
+                .get_selected()
+                .and_then(|(model, iter)| model.get_value(&iter, 7).get::<String>().unwrap());
+            let text = match stack_trace {
+                Some(ref trace) if !trace.is_empty() => trace.as_str(),
+                Some(_) => "No stack trace was recorded for this crash.",
+                None => "",
+            };
+            stack_trace_view.get_buffer().unwrap().set_text(text);
+        });
+
+        {
+            let store = store.clone();
+            refresh_button.connect_clicked(move |_| update_crashes(&store, usermode));
+        }
+
+        {
+            let store = store.clone();
+            info_stack.connect_property_visible_child_notify(move |stack| {
+                if stack.get_visible_child_name().as_deref() == Some("Crashes") {
+                    update_crashes(&store, usermode);
+                }
+            });
+        }
+
+        // The crashes linked from the status of a unit are shown and selected in the list.
+        let info_stack = info_stack.clone();
+        Rc::new(move |cursor: &str| {
+            info_stack.set_visible_child_name("Crashes");
+            let mut iter = store.get_iter_first();
+            while let Some(row) = iter {
+                let row_cursor = store.get_value(&row, 5).get::<String>().unwrap();
+                if row_cursor.as_deref() == Some(cursor) {
+                    tree.get_selection().select_iter(&row);
+                    if let Some(path) = store.get_path(&row) {
+                        tree.scroll_to_cell(
+                            Some(&path),
+                            None::<&gtk::TreeViewColumn>,
+                            true,
+                            0.5,
+                            0.0,
+                        );
+                    }
+                    break;
+                }
+                iter = if store.iter_next(&row) {
+                    Some(row)
+                } else {
+                    None
+                };
+            }
+        })
+    };
 
-    let builder = gtk::Builder::from_string(include_str!("interface.glade"));
-    let window: gtk::Window = builder.get_object("main_window").unwrap();
-    let unit_stack: gtk::Stack = builder.get_object("unit_stack").unwrap();
-    let services_list: gtk::ListBox = builder.get_object("services_list").unwrap();
-    let sockets_list: gtk::ListBox = builder.get_object("sockets_list").unwrap();
-    let timers_list: gtk::ListBox = builder.get_object("timers_list").unwrap();
-    let unit_info: gtk::TextView = builder.get_object("unit_info").unwrap();
-    let ablement_switch: gtk::Switch = builder.get_object("ablement_switch").unwrap();
-    let start_button: gtk::Button = builder.get_object("start_button").unwrap();
-    let stop_button: gtk::Button = builder.get_object("stop_button").unwrap();
-    let save_unit_file: gtk::Button = builder.get_object("save_button").unwrap();
-    let unit_menu_label: gtk::Label = builder.get_object("unit_menu_label").unwrap();
-    let unit_popover: gtk::PopoverMenu = builder.get_object("unit_menu_popover").unwrap();
-    let services_button: gtk::Button = builder.get_object("services_button").unwrap();
-    let sockets_button: gtk::Button = builder.get_object("sockets_button").unwrap();
-    let timers_button: gtk::Button = builder.get_object("timers_button").unwrap();
-    let unit_journal: gtk::TextView = builder.get_object("unit_journal_view").unwrap();
-    let refresh_log_button: gtk::Button = builder.get_object("refresh_log_button").unwrap();
-    let right_header: gtk::Label = builder.get_object("header_service_label").unwrap();
+    // NOTE: Dependency Graph
+    let graph_canvas: gtk::DrawingArea = builder.get_object("graph_canvas").unwrap();
+    let graph_depth: gtk::SpinButton = builder.get_object("graph_depth_spin").unwrap();
+    let graph = Rc::new(RefCell::new(Graph::default()));
+    let graph_zoom = Rc::new(Cell::new(1.0));
 
     {
-        // NOTE: Services Menu Button
-        let label = unit_menu_label.clone();
-        let stack = unit_stack.clone();
-        let popover = unit_popover.clone();
-        services_button.connect_clicked(move |_| {
-            stack.set_visible_child_name("Services");
-            label.set_text("Services");
-            popover.set_visible(false);
+        let graph = graph.clone();
+        let zoom = graph_zoom.clone();
+        graph_canvas.connect_draw(move |_, cr| {
+            draw_graph(cr, &graph.borrow(), zoom.get());
+            gtk::Inhibit(false)
         });
     }
 
+    // Rebuilds the dependency graph of the selected unit.
+    let rebuild_graph: Rc<dyn Fn()> = {
+        let canvas = graph_canvas.clone();
+        let depth = graph_depth.clone();
+        let graph = graph.clone();
+        let zoom = graph_zoom.clone();
+        let selected_unit = selected_unit.clone();
+        let handle = handle.clone();
+        Rc::new(move || {
+            if let Some(ref unit) = *selected_unit.borrow() {
+                match Graph::build(&handle, unit, depth.get_value_as_int() as usize) {
+                    Ok(built) => {
+                        *graph.borrow_mut() = built;
+                        resize_graph(&canvas, &graph.borrow(), zoom.get());
+                    }
+                    Err(error) => println!("{}", error),
+                }
+            }
+        })
+    };
+
     {
-        // NOTE: Sockets Menu Button
-        let label = unit_menu_label.clone();
-        let stack = unit_stack.clone();
-        let popover = unit_popover.clone();
-        sockets_button.connect_clicked(move |_| {
-            stack.set_visible_child_name("Sockets");
-            label.set_text("Sockets");
-            popover.set_visible(false);
+        // Only load the graph, processes and control groups once they are shown, as the graph
+        // takes a dbus call per unit.
+        let graph = graph.clone();
+        let selected_unit = selected_unit.clone();
+        let rebuild_graph = rebuild_graph.clone();
+        let processes_store = processes_store.clone();
+        let cgtop_store = cgtop_store.clone();
+        let cgtop_samples = cgtop_samples.clone();
+        let handle = handle.clone();
+        info_stack.connect_property_visible_child_notify(move |stack| {
+            match stack.get_visible_child_name().as_deref() {
+                Some("Graph") => {
+                    let root = graph.borrow().nodes.first().map(|x| x.unit.clone());
+                    if root != *selected_unit.borrow() {
+                        rebuild_graph();
+                    }
+                }
+                Some("Processes") => {
+                    if let Some(ref unit) = *selected_unit.borrow() {
+                        update_processes(&processes_store, &handle, unit);
+                    }
+                }
+                Some("Top") => update_cgtop(&cgtop_store, &cgtop_samples),
+                _ => (),
+            }
         });
     }
 
     {
-        // NOTE: Timers Menu Button
-        let label = unit_menu_label.clone();
-        let stack = unit_stack.clone();
-        let popover = unit_popover.clone();
-        timers_button.connect_clicked(move |_| {
-            stack.set_visible_child_name("Timers");
-            label.set_text("Timers");
-            popover.set_visible(false);
+        let rebuild_graph = rebuild_graph.clone();
+        let refresh_button: gtk::Button = builder.get_object("graph_refresh_button").unwrap();
+        refresh_button.connect_clicked(move |_| rebuild_graph());
+    }
+
+    {
+        let rebuild_graph = rebuild_graph.clone();
+        graph_depth
+            .get_adjustment()
+            .connect_value_changed(move |_| rebuild_graph());
+    }
+
+    for &(id, factor) in [
+        ("graph_zoom_out_button", 0.8),
+        ("graph_zoom_reset_button", 0.0),
+        ("graph_zoom_in_button", 1.25),
+    ]
+    .iter()
+    {
+        let button: gtk::Button = builder.get_object(id).unwrap();
+        let canvas = graph_canvas.clone();
+        let graph = graph.clone();
+        let zoom = graph_zoom.clone();
+        button.connect_clicked(move |_| {
+            // A factor of zero resets the zoom level.
+            zoom.set(if factor == 0.0 {
+                1.0
+            } else {
+                zoom.get() * factor
+            });
+            resize_graph(&canvas, &graph.borrow(), zoom.get());
         });
     }
 
-    // Setup the Analyze stack
-    setup_systemd_analyze(&builder);
+    for &(id, extension) in [
+        ("graph_export_dot_button", "dot"),
+        ("graph_export_svg_button", "svg"),
+    ]
+    .iter()
+    {
+        let button: gtk::Button = builder.get_object(id).unwrap();
+        let window = window.clone();
+        let graph = graph.clone();
+        button.connect_clicked(move |_| {
+            let graph = graph.borrow();
+            let root = match graph.nodes.first() {
+                Some(root) => root.unit.clone(),
+                None => return,
+            };
+            let contents = match extension {
+                "dot" => graph.to_dot(),
+                _ => graph.to_svg(),
+            };
+            let filename = format!("{}.{}", root, extension);
+            export_file(&window, "Export Graph", &filename, contents.as_bytes());
+        });
+    }
 
-    let handle = dbus::DbusHandle::new(config.bus_type);
-    let handle = std::rc::Rc::new(handle);
-    let usermode = config.user();
+    // Updates the views which are specific to the selected unit.
+    let on_unit_selected: Rc<dyn Fn(&str)> = {
+        let status_grid = status_grid.clone();
+        let dependency_tree = dependency_tree.clone();
+        let dependency_store = dependency_store.clone();
+        let reverse_toggle = reverse_toggle.clone();
+        let ordering_toggle = ordering_toggle.clone();
+        let info_stack = info_stack.clone();
+        let rebuild_graph = rebuild_graph.clone();
+        let processes_store = processes_store.clone();
+        let resources_canvas = resources_canvas.clone();
+        let resources_history = resources_history.clone();
+        let selected_unit = selected_unit.clone();
+        let calendar_button = calendar_button.clone();
+        let unit_file_error = unit_file_error.clone();
+        let follow_log_button = follow_log_button.clone();
+        let show_crash = show_crash.clone();
+        let field_filters_box: gtk::Box = builder.get_object("journal_field_filters_box").unwrap();
+        let clear_field_filters_button: gtk::Button = builder
+            .get_object("journal_clear_field_filters_button")
+            .unwrap();
+        let handle = handle.clone();
+        Rc::new(move |unit: &str| {
+            *selected_unit.borrow_mut() = Some(unit.to_owned());
+            follow_log_button.set_active(false);
+            // Field values and runs only make sense for the unit they were chosen from.
+            if field_filters_box.get_visible() {
+                clear_field_filters_button.clicked();
+            }
+            calendar_button.set_visible(unit.ends_with(".timer"));
+            unit_file_error.set_text("");
+            update_status(&status_grid, &handle, unit, usermode, &show_crash);
+            resources_history.borrow_mut().clear();
+            if let Ok(properties) = handle.get_unit_properties(unit, "") {
+                resources_history
+                    .borrow_mut()
+                    .push(Sample::from_properties(&properties));
+            }
+            resources_canvas.queue_draw();
+            update_dependencies(
+                &dependency_tree,
+                &dependency_store,
+                &handle,
+                unit,
+                reverse_toggle.get_active(),
+                ordering_toggle.get_active(),
+            );
+            match info_stack.get_visible_child_name().as_deref() {
+                Some("Graph") => rebuild_graph(),
+                Some("Processes") => update_processes(&processes_store, &handle, unit),
+                _ => (),
+            }
+        })
+    };
 
     // List of all unit files on the system
     let unit_files = handle.list_unit_files();
@@ -204,10 +2977,15 @@ pub fn launch(config: Config) {
         let unit_info = unit_info.clone();
         let ablement_switch = ablement_switch.clone();
         let unit_journal = unit_journal.clone();
+        let journal_filter = journal_filter.clone();
         let header = right_header.clone();
         let handle = handle.clone();
+        let on_unit_selected = on_unit_selected.clone();
         services_list.connect_row_selected(move |_, row| {
-            let index = row.unwrap().get_index();
+            let index = match row {
+                Some(row) => row.get_index(),
+                None => return,
+            };
             let service = &services[index as usize];
             let description = get_unit_info(&service.name);
             unit_info
@@ -216,8 +2994,14 @@ pub fn launch(config: Config) {
                 .set_text(description.as_str());
             ablement_switch.set_active(handle.get_unit_file_state(get_filename(&service.name)));
             ablement_switch.set_state(ablement_switch.get_active());
-            update_journal(&unit_journal, &service.name, usermode);
+            update_journal(
+                &unit_journal,
+                &journal_filter.borrow(),
+                &service.name,
+                usermode,
+            );
             header.set_label(get_filename(&service.name));
+            on_unit_selected(get_filename(&service.name));
         });
     }
 
@@ -241,10 +3025,15 @@ pub fn launch(config: Config) {
         let unit_info = unit_info.clone();
         let ablement_switch = ablement_switch.clone();
         let unit_journal = unit_journal.clone();
+        let journal_filter = journal_filter.clone();
         let header = right_header.clone();
         let handle = handle.clone();
+        let on_unit_selected = on_unit_selected.clone();
         sockets_list.connect_row_selected(move |_, row| {
-            let index = row.unwrap().get_index();
+            let index = match row {
+                Some(row) => row.get_index(),
+                None => return,
+            };
             let socket = &sockets[index as usize];
             let description = get_unit_info(socket.name.as_str());
             unit_info
@@ -254,8 +3043,14 @@ pub fn launch(config: Config) {
             ablement_switch
                 .set_active(handle.get_unit_file_state(get_filename(socket.name.as_str())));
             ablement_switch.set_state(true);
-            update_journal(&unit_journal, socket.name.as_str(), usermode);
+            update_journal(
+                &unit_journal,
+                &journal_filter.borrow(),
+                socket.name.as_str(),
+                usermode,
+            );
             header.set_label(get_filename(socket.name.as_str()));
+            on_unit_selected(get_filename(&socket.name));
         });
     }
 
@@ -279,10 +3074,15 @@ pub fn launch(config: Config) {
         let unit_info = unit_info.clone();
         let ablement_switch = ablement_switch.clone();
         let unit_journal = unit_journal.clone();
+        let journal_filter = journal_filter.clone();
         let header = right_header.clone();
         let handle = handle.clone();
+        let on_unit_selected = on_unit_selected.clone();
         timers_list.connect_row_selected(move |_, row| {
-            let index = row.unwrap().get_index();
+            let index = match row {
+                Some(row) => row.get_index(),
+                None => return,
+            };
             let timer = &timers[index as usize];
             let description = get_unit_info(timer.name.as_str());
             unit_info
@@ -291,8 +3091,157 @@ pub fn launch(config: Config) {
                 .set_text(description.as_str());
             ablement_switch.set_active(handle.get_unit_file_state(get_filename(&timer.name)));
             ablement_switch.set_state(true);
-            update_journal(&unit_journal, &timer.name, usermode);
+            update_journal(
+                &unit_journal,
+                &journal_filter.borrow(),
+                &timer.name,
+                usermode,
+            );
             header.set_label(get_filename(&timer.name));
+            on_unit_selected(get_filename(&timer.name));
+        });
+    }
+
+    // NOTE: Transient
+    let transient_units = Rc::new(RefCell::new(Vec::new()));
+    let transient_icons = Rc::new(RefCell::new(Vec::new()));
+    update_transient_list(&transient_list, &handle, &transient_units, &transient_icons);
+
+    {
+        let transient_units = transient_units.clone();
+        let unit_info = unit_info.clone();
+        let ablement_switch = ablement_switch.clone();
+        let unit_journal = unit_journal.clone();
+        let journal_filter = journal_filter.clone();
+        let header = right_header.clone();
+        let on_unit_selected = on_unit_selected.clone();
+        transient_list.connect_row_selected(move |_, row| {
+            let index = match row {
+                Some(row) => row.get_index(),
+                None => return,
+            };
+            let unit = transient_units.borrow()[index as usize].clone();
+            // Transient unit files are removed as soon as the unit is garbage collected.
+            let description = fs::read_to_string(&unit.name).unwrap_or_default();
+            unit_info
+                .get_buffer()
+                .unwrap()
+                .set_text(description.as_str());
+            ablement_switch.set_active(false);
+            ablement_switch.set_state(false);
+            update_journal(
+                &unit_journal,
+                &journal_filter.borrow(),
+                &unit.name,
+                usermode,
+            );
+            header.set_label(get_filename(&unit.name));
+            on_unit_selected(get_filename(&unit.name));
+        });
+    }
+
+    {
+        // NOTE: Draw the resource usage charts
+        let history = resources_history.clone();
+        resources_canvas.connect_draw(move |canvas, cr| {
+            let width = f64::from(canvas.get_allocated_width());
+            let height = f64::from(canvas.get_allocated_height());
+            draw_resources(cr, width, height, &history.borrow());
+            gtk::Inhibit(false)
+        });
+    }
+
+    {
+        // NOTE: Refresh the views of the selected unit while they are being viewed. The resource
+        // usage of the unit is always sampled, so that its history is complete when shown.
+        let info_stack = info_stack.clone();
+        let status_grid = status_grid.clone();
+        let processes_store = processes_store.clone();
+        let resources_canvas = resources_canvas.clone();
+        let resources_history = resources_history.clone();
+        let cgtop_store = cgtop_store.clone();
+        let cgtop_samples = cgtop_samples.clone();
+        let selected_unit = selected_unit.clone();
+        let handle = handle.clone();
+        glib::timeout_add_seconds_local(2, move || {
+            let visible = info_stack.get_visible_child_name();
+            if let Some(ref unit) = *selected_unit.borrow() {
+                if let Ok(properties) = handle.get_unit_properties(unit, "") {
+                    resources_history
+                        .borrow_mut()
+                        .push(Sample::from_properties(&properties));
+                }
+                match visible.as_deref() {
+                    Some("Status") => {
+                        update_status(&status_grid, &handle, unit, usermode, &show_crash)
+                    }
+                    Some("Processes") => update_processes(&processes_store, &handle, unit),
+                    Some("Resources") => resources_canvas.queue_draw(),
+                    _ => (),
+                }
+            }
+            if visible.as_deref() == Some("Top") {
+                update_cgtop(&cgtop_store, &cgtop_samples);
+            }
+            glib::Continue(true)
+        });
+    }
+
+    {
+        // NOTE: Processes Refresh Button
+        let processes_store = processes_store.clone();
+        let selected_unit = selected_unit.clone();
+        let handle = handle.clone();
+        let refresh_button: gtk::Button = builder.get_object("refresh_processes_button").unwrap();
+        refresh_button.connect_clicked(move |_| {
+            if let Some(ref unit) = *selected_unit.borrow() {
+                update_processes(&processes_store, &handle, unit);
+            }
+        });
+    }
+
+    {
+        // NOTE: Load the dependencies of a unit when its row is expanded
+        let store = dependency_store.clone();
+        let reverse_toggle = reverse_toggle.clone();
+        let ordering_toggle = ordering_toggle.clone();
+        let handle = handle.clone();
+        dependency_tree.connect_test_expand_row(move |_, iter, _| {
+            if !store.get_value(iter, 3).get_some::<bool>().unwrap() {
+                if let Some(placeholder) = store.iter_children(Some(iter)) {
+                    while store.remove(&placeholder) {}
+                }
+                append_dependencies(
+                    &store,
+                    &handle,
+                    iter,
+                    reverse_toggle.get_active(),
+                    ordering_toggle.get_active(),
+                );
+            }
+            gtk::Inhibit(false)
+        });
+    }
+
+    for toggle in &[reverse_toggle.clone(), ordering_toggle.clone()] {
+        // NOTE: Rebuild the dependency tree when the reverse or ordering modes change
+        let dependency_tree = dependency_tree.clone();
+        let dependency_store = dependency_store.clone();
+        let reverse_toggle = reverse_toggle.clone();
+        let ordering_toggle = ordering_toggle.clone();
+        let selected_unit = selected_unit.clone();
+        let handle = handle.clone();
+        toggle.connect_toggled(move |_| {
+            if let Some(ref unit) = *selected_unit.borrow() {
+                update_dependencies(
+                    &dependency_tree,
+                    &dependency_store,
+                    &handle,
+                    unit,
+                    reverse_toggle.get_active(),
+                    ordering_toggle.get_active(),
+                );
+            }
         });
     }
 
@@ -309,8 +3258,11 @@ pub fn launch(config: Config) {
         ablement_switch.connect_state_set(move |switch, enabled| {
             match unit_stack.get_visible_child_name().unwrap().as_str() {
                 "Services" => {
-                    let index = services_list.get_selected_row().unwrap().get_index();
-                    let service = &services[index as usize];
+                    let index = match selected_index(&services_list) {
+                        Some(index) => index,
+                        None => return gtk::Inhibit(true),
+                    };
+                    let service = &services[index];
                     let service_name = get_filename(&service.name);
                     if enabled && !handle.get_unit_file_state(service_name) {
                         handle.enable_unit_files(service_name);
@@ -321,8 +3273,11 @@ pub fn launch(config: Config) {
                     }
                 }
                 "Sockets" => {
-                    let index = sockets_list.get_selected_row().unwrap().get_index();
-                    let socket = &sockets[index as usize];
+                    let index = match selected_index(&sockets_list) {
+                        Some(index) => index,
+                        None => return gtk::Inhibit(true),
+                    };
+                    let socket = &sockets[index];
                     let socket_name = get_filename(&socket.name);
                     if enabled && !handle.get_unit_file_state(socket_name) {
                         handle.enable_unit_files(socket_name);
@@ -333,8 +3288,11 @@ pub fn launch(config: Config) {
                     }
                 }
                 "Timers" => {
-                    let index = timers_list.get_selected_row().unwrap().get_index();
-                    let timer = &timers[index as usize];
+                    let index = match selected_index(&timers_list) {
+                        Some(index) => index,
+                        None => return gtk::Inhibit(true),
+                    };
+                    let timer = &timers[index];
                     let timer_name = get_filename(&timer.name);
 
                     if enabled && !handle.get_unit_file_state(timer_name) {
@@ -345,6 +3303,8 @@ pub fn launch(config: Config) {
                         switch.set_state(false);
                     }
                 }
+                // Transient units only exist at runtime and can't be enabled.
+                "Transient" => (),
                 _ => unreachable!(),
             }
             gtk::Inhibit(true)
@@ -352,7 +3312,7 @@ pub fn launch(config: Config) {
     }
 
     {
-        // NOTE: Implement the start button
+        // NOTE: Implement the start button, which starts every selected unit
         let services = services.clone();
         let services_list = services_list.clone();
         let sockets = sockets.clone();
@@ -362,38 +3322,40 @@ pub fn launch(config: Config) {
         let services_icons = services_icons.clone();
         let sockets_icons = sockets_icons.clone();
         let timers_icons = timers_icons.clone();
+        let transient_list = transient_list.clone();
+        let transient_units = transient_units.clone();
+        let transient_icons = transient_icons.clone();
         let unit_stack = unit_stack.clone();
         let handle = handle.clone();
         start_button.connect_clicked(move |_| {
-            match unit_stack.get_visible_child_name().unwrap().as_str() {
-                "Services" => {
-                    let index = services_list.get_selected_row().unwrap().get_index();
-                    let service = &services[index as usize];
-                    if handle.start_unit(get_filename(&service.name)).is_none() {
-                        update_icon(&services_icons[index as usize], true);
-                    }
-                }
-                "Sockets" => {
-                    let index = sockets_list.get_selected_row().unwrap().get_index();
-                    let socket = &sockets[index as usize];
-                    if handle.start_unit(get_filename(&socket.name)).is_none() {
-                        update_icon(&sockets_icons[index as usize], true);
+            // The icons of unit files show whether they are enabled, which doesn't change.
+            let units = match unit_stack.get_visible_child_name().unwrap().as_str() {
+                "Services" => selected_units(&services_list, &services, &services_icons),
+                "Sockets" => selected_units(&sockets_list, &sockets, &sockets_icons),
+                "Timers" => selected_units(&timers_list, &timers, &timers_icons),
+                "Transient" => {
+                    let units = selected_units(
+                        &transient_list,
+                        &transient_units.borrow(),
+                        &transient_icons.borrow(),
+                    );
+                    for (unit, icon) in units {
+                        if handle.start_unit(&unit).is_none() {
+                            update_icon(&icon, true);
+                        }
                     }
+                    return;
                 }
-                "Timers" => {
-                    let index = timers_list.get_selected_row().unwrap().get_index();
-                    let timer = &timers[index as usize];
-                    if handle.start_unit(get_filename(&timer.name)).is_none() {
-                        update_icon(&timers_icons[index as usize], true);
-                    }
-                }
-                _ => (),
+                _ => return,
+            };
+            for (unit, _) in units {
+                handle.start_unit(&unit);
             }
         });
     }
 
     {
-        // NOTE: Implement the stop button
+        // NOTE: Implement the stop button, which stops every selected unit
         let services = services.clone();
         let services_list = services_list.clone();
         let sockets = sockets.clone();
@@ -403,38 +3365,154 @@ pub fn launch(config: Config) {
         let services_icons = services_icons.clone();
         let sockets_icons = sockets_icons.clone();
         let timers_icons = timers_icons.clone();
+        let transient_list = transient_list.clone();
+        let transient_units = transient_units.clone();
+        let transient_icons = transient_icons.clone();
         let unit_stack = unit_stack.clone();
+        let handle = handle.clone();
         stop_button.connect_clicked(move |_| {
-            match unit_stack.get_visible_child_name().unwrap().as_str() {
-                "Services" => {
-                    let index = services_list.get_selected_row().unwrap().get_index();
-                    let service = &services[index as usize];
-                    if handle.stop_unit(get_filename(&service.name)).is_none() {
-                        update_icon(&services_icons[index as usize], false);
+            // The icons of unit files show whether they are enabled, which doesn't change.
+            let units = match unit_stack.get_visible_child_name().unwrap().as_str() {
+                "Services" => selected_units(&services_list, &services, &services_icons),
+                "Sockets" => selected_units(&sockets_list, &sockets, &sockets_icons),
+                "Timers" => selected_units(&timers_list, &timers, &timers_icons),
+                "Transient" => {
+                    let units = selected_units(
+                        &transient_list,
+                        &transient_units.borrow(),
+                        &transient_icons.borrow(),
+                    );
+                    for (unit, icon) in units {
+                        if handle.stop_unit(&unit).is_none() {
+                            update_icon(&icon, false);
+                        }
                     }
+                    return;
                 }
-                "Sockets" => {
-                    let index = sockets_list.get_selected_row().unwrap().get_index();
-                    let socket = &sockets[index as usize];
-                    if handle.stop_unit(get_filename(&socket.name)).is_none() {
-                        update_icon(&sockets_icons[index as usize], false);
-                    }
-                }
-                "Timers" => {
-                    let index = timers_list.get_selected_row().unwrap().get_index();
-                    let timer = &timers[index as usize];
-                    if handle.stop_unit(get_filename(&timer.name)).is_none() {
-                        update_icon(&timers_icons[index as usize], false);
-                    }
-                }
-                _ => (),
+                _ => return,
+            };
+            for (unit, _) in units {
+                handle.stop_unit(&unit);
+            }
+        });
+    }
+
+    {
+        // NOTE: Batch Actions
+        let batch_popover: gtk::PopoverMenu = builder.get_object("batch_menu_popover").unwrap();
+        let actions = [
+            ("batch_start_button", BatchAction::Start),
+            ("batch_stop_button", BatchAction::Stop),
+            ("batch_restart_button", BatchAction::Restart),
+            ("batch_enable_button", BatchAction::Enable),
+            ("batch_disable_button", BatchAction::Disable),
+            ("batch_mask_button", BatchAction::Mask),
+        ];
+        for &(id, action) in actions.iter() {
+            let button: gtk::Button = builder.get_object(id).unwrap();
+            let window = window.clone();
+            let popover = batch_popover.clone();
+            let services = services.clone();
+            let services_list = services_list.clone();
+            let sockets = sockets.clone();
+            let sockets_list = sockets_list.clone();
+            let timers = timers.clone();
+            let timers_list = timers_list.clone();
+            let services_icons = services_icons.clone();
+            let sockets_icons = sockets_icons.clone();
+            let timers_icons = timers_icons.clone();
+            let transient_list = transient_list.clone();
+            let transient_units = transient_units.clone();
+            let transient_icons = transient_icons.clone();
+            let unit_stack = unit_stack.clone();
+            let handle = handle.clone();
+            button.connect_clicked(move |_| {
+                popover.set_visible(false);
+                // The icons of unit files show whether they are enabled, while those of transient
+                // units, which can't be enabled, show whether they are active.
+                let (units, state) = match unit_stack.get_visible_child_name().unwrap().as_str() {
+                    "Services" => (
+                        selected_units(&services_list, &services, &services_icons),
+                        action.enablement(),
+                    ),
+                    "Sockets" => (
+                        selected_units(&sockets_list, &sockets, &sockets_icons),
+                        action.enablement(),
+                    ),
+                    "Timers" => (
+                        selected_units(&timers_list, &timers, &timers_icons),
+                        action.enablement(),
+                    ),
+                    "Transient" => (
+                        selected_units(
+                            &transient_list,
+                            &transient_units.borrow(),
+                            &transient_icons.borrow(),
+                        ),
+                        action.activity(),
+                    ),
+                    _ => unreachable!(),
+                };
+                run_batch(&window, handle.clone(), action, units, state);
+            });
+        }
+    }
+
+    {
+        // NOTE: Resource Limits Button
+        let window = window.clone();
+        let selected_unit = selected_unit.clone();
+        let handle = handle.clone();
+        let button: gtk::Button = builder.get_object("resource_control_button").unwrap();
+        button.connect_clicked(move |_| {
+            if let Some(ref unit) = *selected_unit.borrow() {
+                run_resource_control(&window, &handle, unit);
+            }
+        });
+    }
+
+    {
+        // NOTE: Run Transient Unit Button
+        let window = window.clone();
+        let handle = handle.clone();
+        let unit_stack = unit_stack.clone();
+        let unit_menu_label = unit_menu_label.clone();
+        let transient_list = transient_list.clone();
+        let transient_units = transient_units.clone();
+        let transient_icons = transient_icons.clone();
+        let button: gtk::Button = builder.get_object("run_transient_button").unwrap();
+        button.connect_clicked(move |_| {
+            let unit = match run_transient(&window, &handle) {
+                Some(unit) => unit,
+                None => return,
+            };
+            update_transient_list(&transient_list, &handle, &transient_units, &transient_icons);
+            unit_stack.set_visible_child_name("Transient");
+            unit_menu_label.set_text("Transient");
+            let index = transient_units
+                .borrow()
+                .iter()
+                .position(|x| get_filename(&x.name) == unit);
+            if let Some(row) = index.and_then(|x| transient_list.get_row_at_index(x as i32)) {
+                transient_list.unselect_all();
+                transient_list.select_row(Some(&row));
             }
         });
     }
 
+    {
+        // NOTE: Timer Schedule Button
+        let window = window.clone();
+        let unit_info = unit_info.clone();
+        calendar_button.connect_clicked(move |_| {
+            edit_calendar(&window, &unit_info.get_buffer().unwrap());
+        });
+    }
+
     {
         // NOTE: Save Button
         let unit_info = unit_info.clone();
+        let unit_file_error = unit_file_error.clone();
         let services = services.clone();
         let services_list = services_list.clone();
         let sockets = sockets.clone();
@@ -448,17 +3526,33 @@ pub fn launch(config: Config) {
             let end = buffer.get_end_iter();
             let text = buffer.get_text(&start, &end, true).unwrap();
             let path = match unit_stack.get_visible_child_name().unwrap().as_str() {
-                "Services" => {
-                    &services[services_list.get_selected_row().unwrap().get_index() as usize].name
-                }
-                "Sockets" => {
-                    &sockets[sockets_list.get_selected_row().unwrap().get_index() as usize].name
-                }
-                "Timers" => {
-                    &timers[timers_list.get_selected_row().unwrap().get_index() as usize].name
-                }
+                "Services" => selected_index(&services_list).map(|index| &services[index].name),
+                "Sockets" => selected_index(&sockets_list).map(|index| &sockets[index].name),
+                "Timers" => selected_index(&timers_list).map(|index| &timers[index].name),
+                // Transient unit files are generated by systemd and can't be edited.
+                "Transient" => None,
                 _ => unreachable!(),
             };
+            let path = match path {
+                Some(path) => path,
+                None => return,
+            };
+            // Timers with an invalid calendar expression would fail to load.
+            if path.ends_with(".timer") {
+                let errors: Vec<String> = calendar::unit_file_expressions(text.as_str())
+                    .into_iter()
+                    .filter(|(_, expression)| calendar::unsupported(expression).is_none())
+                    .filter_map(|(line, expression)| {
+                        calendar::parse(&expression)
+                            .err()
+                            .map(|error| format!("Line {}: {}", line + 1, error))
+                    })
+                    .collect();
+                unit_file_error.set_text(&errors.join("; "));
+                if !errors.is_empty() {
+                    return;
+                }
+            }
             match fs::OpenOptions::new().write(true).open(path) {
                 Ok(mut file) => {
                     if let Err(message) = file.write(text.as_bytes()) {
@@ -470,6 +3564,396 @@ pub fn launch(config: Config) {
         });
     }
 
+    {
+        // NOTE: Journal Filters
+        let priority_combo: gtk::ComboBoxText =
+            builder.get_object("journal_priority_combo").unwrap();
+        let boot_combo: gtk::ComboBoxText = builder.get_object("journal_boot_combo").unwrap();
+        let since_entry: gtk::Entry = builder.get_object("journal_since_entry").unwrap();
+        let until_entry: gtk::Entry = builder.get_object("journal_until_entry").unwrap();
+        let field_filters_box: gtk::Box = builder.get_object("journal_field_filters_box").unwrap();
+        let field_filters_label: gtk::Label =
+            builder.get_object("journal_field_filters_label").unwrap();
+        let clear_button: gtk::Button = builder
+            .get_object("journal_clear_field_filters_button")
+            .unwrap();
+
+        priority_combo.append(Some("all"), "All priorities");
+        for (priority, name) in journal::PRIORITIES.iter().enumerate() {
+            priority_combo.append(Some(&priority.to_string()), &format!("{} and above", name));
+        }
+        priority_combo.set_active_id(Some("all"));
+        boot_combo.append(Some("current"), "Current boot");
+        boot_combo.set_active_id(Some("current"));
+
+        // Reads the filter from the controls, and shows the journal of the selected unit anew.
+        let apply_filter: Rc<dyn Fn()> = {
+            let priority_combo = priority_combo.clone();
+            let boot_combo = boot_combo.clone();
+            let since_entry = since_entry.clone();
+            let until_entry = until_entry.clone();
+            let unit_journal = unit_journal.clone();
+            let journal_filter = journal_filter.clone();
+            let selected_unit = selected_unit.clone();
+            let field_filters_box = field_filters_box.clone();
+            let field_filters_label = field_filters_label.clone();
+            Rc::new(move || {
+                let now = timers::now();
+                let parse = |entry: &gtk::Entry| {
+                    let text = entry.get_text();
+                    if text.trim().is_empty() {
+                        Ok(None)
+                    } else {
+                        journal::parse_time(&text, now).map(Some)
+                    }
+                };
+                let filter = match (parse(&since_entry), parse(&until_entry)) {
+                    (Ok(since), Ok(until)) => Filter {
+                        priority: priority_combo.get_active_id().and_then(|x| x.parse().ok()),
+                        since,
+                        until,
+                        boot: boot_combo
+                            .get_active_id()
+                            .filter(|x| x != "current")
+                            .map(|x| x.to_string()),
+                        matches: journal_filter.borrow().matches.clone(),
+                        invocation: journal_filter.borrow().invocation.clone(),
+                    },
+                    (Err(error), _) | (_, Err(error)) => {
+                        unit_journal.status.set_text(&error);
+                        return;
+                    }
+                };
+                let matches: Vec<String> = filter
+                    .invocation
+                    .iter()
+                    .map(|x| format!("INVOCATION_ID={}", x))
+                    .chain(
+                        filter
+                            .matches
+                            .iter()
+                            .map(|(field, value)| format!("{}={}", field, value)),
+                    )
+                    .collect();
+                field_filters_label.set_text(&format!("Only entries with {}", matches.join(" ")));
+                field_filters_box.set_visible(!matches.is_empty());
+                *journal_filter.borrow_mut() = filter;
+                if let Some(ref unit) = *selected_unit.borrow() {
+                    update_journal(&unit_journal, &journal_filter.borrow(), unit, usermode);
+                }
+            })
+        };
+
+        {
+            let apply_filter = apply_filter.clone();
+            priority_combo.connect_changed(move |_| apply_filter());
+        }
+        {
+            let apply_filter = apply_filter.clone();
+            boot_combo.connect_changed(move |_| apply_filter());
+        }
+        for entry in [&since_entry, &until_entry].iter() {
+            let apply_filter = apply_filter.clone();
+            entry.connect_activate(move |_| apply_filter());
+        }
+        {
+            let journal_filter = journal_filter.clone();
+            let apply_filter = apply_filter.clone();
+            clear_button.connect_clicked(move |_| {
+                {
+                    let mut filter = journal_filter.borrow_mut();
+                    filter.matches.clear();
+                    filter.invocation = None;
+                }
+                apply_filter();
+            });
+        }
+
+        {
+            // NOTE: Journal Runs Button
+            let runs_button: gtk::Button = builder.get_object("journal_runs_button").unwrap();
+            let window = window.clone();
+            let journal_filter = journal_filter.clone();
+            let selected_unit = selected_unit.clone();
+            let apply_filter = apply_filter.clone();
+            runs_button.connect_clicked(move |_| {
+                let unit = match *selected_unit.borrow() {
+                    Some(ref unit) => unit.clone(),
+                    None => return,
+                };
+                let filter = journal_filter.borrow().clone();
+                if let Some(invocation) = browse_invocations(&window, &unit, usermode, &filter) {
+                    journal_filter.borrow_mut().invocation = Some(invocation);
+                    apply_filter();
+                }
+            });
+        }
+
+        // NOTE: Journal Entry Inspector
+        let inspector: gtk::Box = builder.get_object("journal_inspector").unwrap();
+        let fields_tree: gtk::TreeView = builder.get_object("journal_fields_tree").unwrap();
+        let fields_store = setup_journal_fields_tree(&fields_tree);
+        let filter_button: gtk::Button = builder.get_object("journal_field_filter_button").unwrap();
+        {
+            let unit_journal = unit_journal.clone();
+            let inspector = inspector.clone();
+            unit_journal
+                .tree
+                .get_selection()
+                .connect_changed(move |selection| {
+                    let entries = unit_journal.entries.borrow();
+                    let entry = selection.get_selected().and_then(|(model, iter)| {
+                        let index = model.get_value(&iter, 5).get_some::<u32>().unwrap();
+                        entries.get(index as usize)
+                    });
+                    match entry {
+                        Some(entry) => {
+                            update_journal_fields(&fields_store, entry);
+                            inspector.show();
+                        }
+                        None => inspector.hide(),
+                    }
+                });
+        }
+        let journal_filter = journal_filter.clone();
+        filter_button.connect_clicked(move |_| {
+            let (model, iter) = match fields_tree.get_selection().get_selected() {
+                Some(selected) => selected,
+                None => return,
+            };
+            let field = model.get_value(&iter, 0).get::<String>().unwrap().unwrap();
+            let value = model.get_value(&iter, 1).get::<String>().unwrap().unwrap();
+            {
+                let mut filter = journal_filter.borrow_mut();
+                if !filter.matches.contains(&(field.clone(), value.clone())) {
+                    filter.matches.push((field, value));
+                }
+            }
+            apply_filter();
+        });
+
+        // The previous boots are only listed once the journal is first viewed.
+        let listed = Rc::new(Cell::new(false));
+        info_stack.connect_property_visible_child_notify(move |stack| {
+            if listed.get() || stack.get_visible_child_name().as_deref() != Some("Unit Journal") {
+                return;
+            }
+            listed.set(true);
+            let current = history::current_boot_id().unwrap_or_default();
+            match journal::list_boots(usermode) {
+                Ok(boots) => {
+                    for boot in boots.iter().filter(|x| x.id != current) {
+                        let label = format!(
+                            "{} – {}",
+                            status::format_timestamp(boot.first),
+                            status::format_timestamp(boot.last)
+                        );
+                        boot_combo.append(Some(&boot.id), &label);
+                    }
+                }
+                Err(error) => println!("{}", error),
+            }
+        });
+    }
+
+    {
+        // NOTE: Journal Search
+        let search_entry: gtk::SearchEntry = builder.get_object("journal_search_entry").unwrap();
+        let regex_toggle: gtk::CheckButton = builder.get_object("journal_regex_toggle").unwrap();
+        let previous_button: gtk::Button =
+            builder.get_object("journal_previous_match_button").unwrap();
+        let next_button: gtk::Button = builder.get_object("journal_next_match_button").unwrap();
+        let matches_label: gtk::Label = builder.get_object("journal_matches_label").unwrap();
+        // The rows whose message matches the search, and which of them is selected.
+        let matches: Rc<RefCell<Vec<i32>>> = Rc::new(RefCell::new(Vec::new()));
+        let current = Rc::new(Cell::new(0));
+
+        // Selects and scrolls to the row of the match at the given index.
+        let select: Rc<dyn Fn(usize)> = {
+            let unit_journal = unit_journal.clone();
+            let matches = matches.clone();
+            let current = current.clone();
+            let matches_label = matches_label.clone();
+            Rc::new(move |index: usize| {
+                let matches = matches.borrow();
+                if matches.is_empty() {
+                    return;
+                }
+                let index = index % matches.len();
+                current.set(index);
+                let path = gtk::TreePath::from_indicesv(&[matches[index]]);
+                unit_journal.tree.get_selection().select_path(&path);
+                unit_journal.tree.scroll_to_cell(
+                    Some(&path),
+                    None::<&gtk::TreeViewColumn>,
+                    true,
+                    0.5,
+                    0.0,
+                );
+                matches_label.set_text(&format!("{} of {}", index + 1, matches.len()));
+            })
+        };
+
+        // Finds and highlights the matches of the search within the messages of the table.
+        let highlight: Rc<dyn Fn()> = {
+            let unit_journal = unit_journal.clone();
+            let search_entry = search_entry.clone();
+            let regex_toggle = regex_toggle.clone();
+            let matches = matches.clone();
+            let current = current.clone();
+            let matches_label = matches_label.clone();
+            Rc::new(move || {
+                matches_label.set_text("");
+                matches_label.set_tooltip_text(None);
+                let pattern = search_entry.get_text();
+                let search = if pattern.is_empty() {
+                    None
+                } else {
+                    match Search::new(&pattern, regex_toggle.get_active()) {
+                        Ok(search) => Some(search),
+                        Err(error) => {
+                            matches_label.set_text("Invalid");
+                            matches_label.set_tooltip_text(Some(&error));
+                            None
+                        }
+                    }
+                };
+
+                let store = &unit_journal.store;
+                let entries = unit_journal.entries.borrow();
+                let mut rows = Vec::new();
+                let mut row = 0;
+                let mut next = store.get_iter_first();
+                while let Some(iter) = next {
+                    let index = store.get_value(&iter, 5).get_some::<u32>().unwrap();
+                    let message = entries[index as usize].message();
+                    let ranges = search
+                        .as_ref()
+                        .map_or_else(Vec::new, |x| x.find_all(message));
+                    let mut markup = String::new();
+                    let mut written = 0;
+                    for &(start, end) in &ranges {
+                        markup.push_str(&glib::markup_escape_text(&message[written..start]));
+                        markup.push_str("<span background=\"yellow\">");
+                        markup.push_str(&glib::markup_escape_text(&message[start..end]));
+                        markup.push_str("</span>");
+                        written = end;
+                    }
+                    markup.push_str(&glib::markup_escape_text(&message[written..]));
+                    store.set_value(&iter, 3, &markup.to_value());
+                    if !ranges.is_empty() {
+                        rows.push(row);
+                    }
+                    row += 1;
+                    next = Some(iter).filter(|x| store.iter_next(x));
+                }
+
+                if search.is_some() {
+                    if rows.is_empty() {
+                        matches_label.set_text("No matches");
+                    } else {
+                        current.set(current.get().min(rows.len() - 1));
+                        matches_label.set_text(&format!("{} of {}", current.get() + 1, rows.len()));
+                    }
+                }
+                *matches.borrow_mut() = rows;
+            })
+        };
+
+        {
+            let highlight = highlight.clone();
+            let current = current.clone();
+            let select = select.clone();
+            search_entry.connect_search_changed(move |_| {
+                current.set(0);
+                highlight();
+                select(0);
+            });
+        }
+        {
+            let highlight = highlight.clone();
+            regex_toggle.connect_toggled(move |_| highlight());
+        }
+        {
+            // The matches are found again once entries have been read.
+            let pending = Rc::new(Cell::new(false));
+            unit_journal.store.connect_row_inserted(move |_, _, _| {
+                if !pending.replace(true) {
+                    let pending = pending.clone();
+                    let highlight = highlight.clone();
+                    glib::idle_add_local(move || {
+                        pending.set(false);
+                        highlight();
+                        glib::Continue(false)
+                    });
+                }
+            });
+        }
+        for &(button, step) in [(&next_button, 1), (&previous_button, -1)].iter() {
+            let current = current.clone();
+            let matches = matches.clone();
+            let select = select.clone();
+            button.connect_clicked(move |_| {
+                let count = matches.borrow().len() as isize;
+                if count > 0 {
+                    select((current.get() as isize + step).rem_euclid(count) as usize);
+                }
+            });
+        }
+        {
+            let next_button = next_button.clone();
+            search_entry.connect_activate(move |_| next_button.clicked());
+        }
+        search_entry.connect_next_match(move |_| next_button.clicked());
+        search_entry.connect_previous_match(move |_| previous_button.clicked());
+    }
+
+    {
+        // NOTE: Journal Export Button
+        let export_button: gtk::Button = builder.get_object("export_log_button").unwrap();
+        let window = window.clone();
+        let journal_filter = journal_filter.clone();
+        let selected_unit = selected_unit.clone();
+        export_button.connect_clicked(move |_| {
+            if let Some(ref unit) = *selected_unit.borrow() {
+                export_journal(&window, unit, usermode, &journal_filter.borrow());
+            }
+        });
+    }
+
+    {
+        // NOTE: Load older journal entries when nearing the end of the journal view
+        let unit_journal = unit_journal.clone();
+        let adjustment = unit_journal.tree.get_vadjustment().unwrap();
+        adjustment.connect_value_changed(move |adjustment| {
+            let remaining =
+                adjustment.get_upper() - adjustment.get_value() - adjustment.get_page_size();
+            if remaining < adjustment.get_page_size() {
+                load_older_journal(&unit_journal);
+            }
+        });
+    }
+
+    {
+        // NOTE: Follow the journal of the selected unit, reading new entries every second
+        let unit_journal = unit_journal.clone();
+        let follow_source: RefCell<Option<glib::SourceId>> = RefCell::new(None);
+        follow_log_button.connect_toggled(move |button| {
+            if let Some(source) = follow_source.borrow_mut().take() {
+                glib::source_remove(source);
+            }
+            if button.get_active() {
+                load_newer_journal(&unit_journal);
+                let unit_journal = unit_journal.clone();
+                let source = glib::timeout_add_seconds_local(1, move || {
+                    load_newer_journal(&unit_journal);
+                    glib::Continue(true)
+                });
+                *follow_source.borrow_mut() = Some(source);
+            }
+        });
+    }
+
     {
         // NOTE: Journal Refresh Button
         let services = services.clone();
@@ -478,25 +3962,65 @@ pub fn launch(config: Config) {
         let sockets_list = sockets_list.clone();
         let timers = timers.clone();
         let timers_list = timers_list.clone();
+        let transient_list = transient_list.clone();
+        let transient_units = transient_units.clone();
         let unit_stack = unit_stack.clone();
         let refresh_button = refresh_log_button.clone();
         let unit_journal = unit_journal.clone();
+        let journal_filter = journal_filter.clone();
         refresh_button.connect_clicked(move |_| {
             match unit_stack.get_visible_child_name().unwrap().as_str() {
                 "Services" => {
-                    let index = services_list.get_selected_row().unwrap().get_index();
-                    let service = &services[index as usize];
-                    update_journal(&unit_journal, &service.name, usermode);
+                    let index = match selected_index(&services_list) {
+                        Some(index) => index,
+                        None => return,
+                    };
+                    let service = &services[index];
+                    update_journal(
+                        &unit_journal,
+                        &journal_filter.borrow(),
+                        &service.name,
+                        usermode,
+                    );
                 }
                 "Sockets" => {
-                    let index = sockets_list.get_selected_row().unwrap().get_index();
-                    let socket = &sockets[index as usize];
-                    update_journal(&unit_journal, socket.name.as_str(), usermode);
+                    let index = match selected_index(&sockets_list) {
+                        Some(index) => index,
+                        None => return,
+                    };
+                    let socket = &sockets[index];
+                    update_journal(
+                        &unit_journal,
+                        &journal_filter.borrow(),
+                        socket.name.as_str(),
+                        usermode,
+                    );
                 }
                 "Timers" => {
-                    let index = timers_list.get_selected_row().unwrap().get_index();
-                    let timer = &timers[index as usize];
-                    update_journal(&unit_journal, timer.name.as_str(), usermode);
+                    let index = match selected_index(&timers_list) {
+                        Some(index) => index,
+                        None => return,
+                    };
+                    let timer = &timers[index];
+                    update_journal(
+                        &unit_journal,
+                        &journal_filter.borrow(),
+                        timer.name.as_str(),
+                        usermode,
+                    );
+                }
+                "Transient" => {
+                    let index = match selected_index(&transient_list) {
+                        Some(index) => index,
+                        None => return,
+                    };
+                    let unit = &transient_units.borrow()[index];
+                    update_journal(
+                        &unit_journal,
+                        &journal_filter.borrow(),
+                        &unit.name,
+                        usermode,
+                    );
                 }
                 _ => unreachable!(),
             }